@@ -0,0 +1,31 @@
+pub mod curses;
+pub mod headless;
+
+// everything the editor needs from a terminal, so it can run against a real one or an in-memory grid
+pub trait Backend {
+    // (rows, columns) of the whole screen
+    fn size(&self) -> (i32, i32);
+
+    // text is laid out from (row, col) onwards using unicode widths
+    fn draw(&mut self, row: i32, col: i32, text: &str, pair: i16);
+
+    fn move_cursor(&mut self, row: i32, col: i32);
+
+    // a bar cursor marks insert mode, a block everything else
+    fn set_bar_cursor(&mut self, bar: bool);
+
+    // keys are reported the way getch does, multibyte characters one byte at a time
+    fn read_key(&mut self) -> i32;
+
+    // like read_key, but ERR right away when nothing is waiting, which tells a sequence from <Esc>
+    fn read_pending(&mut self) -> i32;
+
+    // puts a key back to be read again, the last one put back comes first
+    fn unread_key(&mut self, key: i32);
+
+    // hands text to the terminal's own clipboard
+    fn set_clipboard(&mut self, text: &str);
+
+    // throws away what is on screen so the next frame is drawn from scratch
+    fn clear(&mut self);
+}
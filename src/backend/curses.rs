@@ -0,0 +1,272 @@
+use std::io::Write;
+
+use base64::Engine;
+
+use crate::backend::Backend;
+use crate::config::Config;
+
+pub struct Curses {
+    screen: ncurses::WINDOW,
+    // the escape sequence is only sent when the shape actually changes
+    bar_cursor: Option<bool>,
+    // how many color pairs the terminal has, the ones past it are drawn with attributes instead
+    pairs: i32,
+}
+
+impl Curses {
+    pub fn new(configuration: &Config) -> Curses {
+        ncurses::setlocale(ncurses::LcCategory::all, "");
+        let screen = ncurses::initscr();
+        ncurses::noecho();
+        ncurses::cbreak();
+        ncurses::raw();
+        ncurses::keypad(screen, true);
+        ncurses::set_escdelay(0);
+        init_colors(configuration);
+
+        // pastes come wrapped in markers instead of looking like typed keys
+        print!("\x1b[?2004h");
+        let _ = std::io::stdout().flush();
+
+        return Curses {
+            screen,
+            bar_cursor: None,
+            pairs: ncurses::COLOR_PAIRS(),
+        };
+    }
+}
+
+impl Backend for Curses {
+    fn size(&self) -> (i32, i32) {
+        return (ncurses::LINES(), ncurses::COLS());
+    }
+
+    fn draw(&mut self, row: i32, col: i32, text: &str, pair: i16) {
+        let attr = if (pair as i32) < self.pairs { ncurses::COLOR_PAIR(pair) } else { fallback(pair) };
+        ncurses::attron(attr);
+        ncurses::mvwaddstr(self.screen, row, col, text);
+        ncurses::attroff(attr);
+    }
+
+    fn move_cursor(&mut self, row: i32, col: i32) {
+        ncurses::wmove(self.screen, row, col);
+    }
+
+    fn set_bar_cursor(&mut self, bar: bool) {
+        if self.bar_cursor == Some(bar) {
+            return;
+        }
+        self.bar_cursor = Some(bar);
+        if bar {
+            println!("\x1b[6 q");
+        } else {
+            println!("\x1b[1 q");
+        }
+    }
+
+    // a blocking getch only fails when interrupted, that is not the end of input
+    fn read_key(&mut self) -> i32 {
+        loop {
+            let key = ncurses::getch();
+            if key != ncurses::ERR {
+                return key;
+            }
+        }
+    }
+
+    fn read_pending(&mut self) -> i32 {
+        ncurses::nodelay(self.screen, true);
+        let key = ncurses::getch();
+        ncurses::nodelay(self.screen, false);
+        return key;
+    }
+
+    fn unread_key(&mut self, key: i32) {
+        ncurses::ungetch(key);
+    }
+
+    // OSC 52, terminals that don't know it ignore it
+    fn set_clipboard(&mut self, text: &str) {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(text);
+        print!("\x1b]52;c;{}\x07", encoded);
+        let _ = std::io::stdout().flush();
+    }
+
+    fn clear(&mut self) {
+        ncurses::clear();
+    }
+}
+
+impl Drop for Curses {
+    fn drop(&mut self) {
+        print!("\x1b[?2004l");
+        let _ = std::io::stdout().flush();
+        ncurses::endwin();
+    }
+}
+
+fn init_colors(configuration: &Config) {
+    ncurses::start_color();
+    // colors are only set where the terminal lets them be changed and has room for all of them,
+    // elsewhere the pairs are made of the standard ones
+    let custom = ncurses::can_change_color() && ncurses::COLORS() > 16;
+    let color = |number: i16| if custom { number } else { standard(number) };
+
+    ncurses::init_color(
+        1,
+        rgb(configuration.foreground.red as f32) as i16,
+        rgb(configuration.foreground.green as f32) as i16,
+        rgb(configuration.foreground.blue as f32) as i16,
+    );
+
+    ncurses::init_color(
+        4,
+        rgb(configuration.foreground1.red as f32) as i16,
+        rgb(configuration.foreground1.green as f32) as i16,
+        rgb(configuration.foreground1.blue as f32) as i16,
+    );
+
+    ncurses::init_color(
+        2,
+        rgb(configuration.background.red as f32) as i16,
+        rgb(configuration.background.green as f32) as i16,
+        rgb(configuration.background.blue as f32) as i16,
+    );
+
+    ncurses::init_color(
+        5,
+        rgb(configuration.background1.red as f32) as i16,
+        rgb(configuration.background1.green as f32) as i16,
+        rgb(configuration.background1.blue as f32) as i16,
+    );
+
+    ncurses::init_color(
+        3,
+        rgb(configuration.green.red as f32) as i16,
+        rgb(configuration.green.green as f32) as i16,
+        rgb(configuration.green.blue as f32) as i16,
+    );
+
+    ncurses::init_color(
+        6,
+        rgb(configuration.orange.red as f32) as i16,
+        rgb(configuration.orange.green as f32) as i16,
+        rgb(configuration.orange.blue as f32) as i16,
+    );
+
+    ncurses::init_color(
+        7,
+        rgb(configuration.yellow.red as f32) as i16,
+        rgb(configuration.yellow.green as f32) as i16,
+        rgb(configuration.yellow.blue as f32) as i16,
+    );
+
+    ncurses::init_color(
+        8,
+        rgb(configuration.quartz.red as f32) as i16,
+        rgb(configuration.quartz.green as f32) as i16,
+        rgb(configuration.quartz.blue as f32) as i16,
+    );
+
+    ncurses::init_color(
+        9,
+        rgb(configuration.number.red as f32) as i16,
+        rgb(configuration.number.green as f32) as i16,
+        rgb(configuration.number.blue as f32) as i16,
+    );
+
+    ncurses::init_color(
+        10,
+        rgb(configuration.char_literal.red as f32) as i16,
+        rgb(configuration.char_literal.green as f32) as i16,
+        rgb(configuration.char_literal.blue as f32) as i16,
+    );
+
+    ncurses::init_color(
+        11,
+        rgb(configuration.lifetime.red as f32) as i16,
+        rgb(configuration.lifetime.green as f32) as i16,
+        rgb(configuration.lifetime.blue as f32) as i16,
+    );
+
+    ncurses::init_color(
+        12,
+        rgb(configuration.macro_call.red as f32) as i16,
+        rgb(configuration.macro_call.green as f32) as i16,
+        rgb(configuration.macro_call.blue as f32) as i16,
+    );
+
+    ncurses::init_color(
+        13,
+        rgb(configuration.attribute.red as f32) as i16,
+        rgb(configuration.attribute.green as f32) as i16,
+        rgb(configuration.attribute.blue as f32) as i16,
+    );
+
+    ncurses::init_color(
+        14,
+        rgb(configuration.doc_comment.red as f32) as i16,
+        rgb(configuration.doc_comment.green as f32) as i16,
+        rgb(configuration.doc_comment.blue as f32) as i16,
+    );
+
+    ncurses::init_color(
+        15,
+        rgb(configuration.selection.red as f32) as i16,
+        rgb(configuration.selection.green as f32) as i16,
+        rgb(configuration.selection.blue as f32) as i16,
+    );
+
+    ncurses::init_color(
+        16,
+        rgb(configuration.search.red as f32) as i16,
+        rgb(configuration.search.green as f32) as i16,
+        rgb(configuration.search.blue as f32) as i16,
+    );
+
+    ncurses::init_pair(1, color(1), color(2));
+    ncurses::init_pair(2, color(2), color(3));
+    ncurses::init_pair(3, color(1), color(5));
+    ncurses::init_pair(4, color(2), color(6));
+    ncurses::init_pair(5, color(7), color(2));
+    ncurses::init_pair(6, color(8), color(2));
+    ncurses::init_pair(7, color(6), color(2));
+    ncurses::init_pair(8, color(3), color(2));
+    ncurses::init_pair(9, color(5), color(2));
+    ncurses::init_pair(10, color(9), color(2));
+    ncurses::init_pair(11, color(10), color(2));
+    ncurses::init_pair(12, color(11), color(2));
+    ncurses::init_pair(13, color(12), color(2));
+    ncurses::init_pair(14, color(13), color(2));
+    ncurses::init_pair(15, color(14), color(2));
+    ncurses::init_pair(16, color(1), color(15));
+    ncurses::init_pair(17, color(2), color(16));
+}
+
+// the standard color nearest to what each of the colors set above is for
+fn standard(number: i16) -> i16 {
+    return match number {
+        2 => ncurses::COLOR_BLACK,
+        3 | 10 => ncurses::COLOR_GREEN,
+        5 | 12 | 14 | 15 => ncurses::COLOR_BLUE,
+        6 => ncurses::COLOR_RED,
+        7 | 13 | 16 => ncurses::COLOR_YELLOW,
+        8 => ncurses::COLOR_MAGENTA,
+        9 | 11 => ncurses::COLOR_CYAN,
+        _ => ncurses::COLOR_WHITE,
+    };
+}
+
+// a pair the terminal has no room for, text is drawn plain and the selection and search matches
+// reversed so they still show, matches bold as well to tell them from the selection
+fn fallback(pair: i16) -> ncurses::attr_t {
+    return match pair {
+        16 => ncurses::A_REVERSE(),
+        17 => ncurses::A_REVERSE() | ncurses::A_BOLD(),
+        _ => ncurses::A_NORMAL(),
+    };
+}
+
+fn rgb(num: f32) -> f32 {
+    return (1000.0 / 100.0) * ((num / 256.0) * 100.0);
+}
@@ -0,0 +1,151 @@
+use std::collections::VecDeque;
+
+use unicode_width::UnicodeWidthChar;
+
+use crate::backend::Backend;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cell {
+    // empty for the cells covered by the right half of a wide character
+    pub text: String,
+    pub pair: i16,
+}
+
+// records every frame into a grid of cells and replays queued keys instead of reading a terminal
+#[derive(Debug)]
+pub struct Headless {
+    rows: i32,
+    cols: i32,
+    cells: Vec<Cell>,
+    cursor: (i32, i32),
+    bar_cursor: bool,
+    keys: VecDeque<i32>,
+    // the last text given to set_clipboard
+    clipboard: Option<String>,
+}
+
+impl Headless {
+    pub fn new(rows: i32, cols: i32) -> Headless {
+        return Headless {
+            rows,
+            cols,
+            cells: vec![Cell { text: String::from(" "), pair: 0 }; (rows * cols) as usize],
+            cursor: (0, 0),
+            bar_cursor: false,
+            keys: VecDeque::new(),
+            clipboard: None,
+        };
+    }
+
+    pub fn feed_key(&mut self, key: i32) {
+        self.keys.push_back(key);
+    }
+
+    // queued byte by byte, the way a terminal sends them
+    pub fn feed_str(&mut self, text: &str) {
+        self.keys.extend(text.bytes().map(|byte| byte as i32));
+    }
+
+    pub fn pending_keys(&self) -> usize {
+        return self.keys.len();
+    }
+
+    // behaves like a terminal being resized, the screen is wiped and KEY_RESIZE is queued
+    pub fn resize(&mut self, rows: i32, cols: i32) {
+        *self = Headless {
+            keys: std::mem::take(&mut self.keys),
+            clipboard: self.clipboard.take(),
+            ..Headless::new(rows, cols)
+        };
+        self.keys.push_back(ncurses::KEY_RESIZE);
+    }
+
+    pub fn cell(&self, row: i32, col: i32) -> &Cell {
+        return &self.cells[(row * self.cols + col) as usize];
+    }
+
+    pub fn line(&self, row: i32) -> String {
+        return (0..self.cols).map(|col| self.cell(row, col).text.as_str()).collect();
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        return (0..self.rows).map(|row| self.line(row)).collect();
+    }
+
+    pub fn cursor(&self) -> (i32, i32) {
+        return self.cursor;
+    }
+
+    pub fn bar_cursor(&self) -> bool {
+        return self.bar_cursor;
+    }
+
+    pub fn clipboard(&self) -> Option<&str> {
+        return self.clipboard.as_deref();
+    }
+}
+
+impl Backend for Headless {
+    fn size(&self) -> (i32, i32) {
+        return (self.rows, self.cols);
+    }
+
+    fn draw(&mut self, row: i32, mut col: i32, text: &str, pair: i16) {
+        if row < 0 || row >= self.rows || col < 0 {
+            return;
+        }
+
+        for character in text.chars() {
+            let width = character.width().unwrap_or(0) as i32;
+            if width == 0 {
+                // combining marks join the character drawn before them
+                if col > 0 {
+                    let index = (row * self.cols + col - 1) as usize;
+                    self.cells[index].text.push(character);
+                }
+                continue;
+            }
+            if col + width > self.cols {
+                return;
+            }
+
+            let index = (row * self.cols + col) as usize;
+            self.cells[index] = Cell { text: character.to_string(), pair };
+            for covered in 1..width as usize {
+                self.cells[index + covered] = Cell { text: String::new(), pair };
+            }
+            col += width;
+        }
+    }
+
+    fn move_cursor(&mut self, row: i32, col: i32) {
+        self.cursor = (row, col);
+    }
+
+    fn set_bar_cursor(&mut self, bar: bool) {
+        self.bar_cursor = bar;
+    }
+
+    // ERR once the queue runs dry, like getch in nodelay mode
+    fn read_key(&mut self) -> i32 {
+        return self.keys.pop_front().unwrap_or(ncurses::ERR);
+    }
+
+    fn read_pending(&mut self) -> i32 {
+        return self.read_key();
+    }
+
+    fn unread_key(&mut self, key: i32) {
+        self.keys.push_front(key);
+    }
+
+    fn set_clipboard(&mut self, text: &str) {
+        self.clipboard = Some(text.to_string());
+    }
+
+    fn clear(&mut self) {
+        for cell in &mut self.cells {
+            *cell = Cell { text: String::from(" "), pair: 0 };
+        }
+    }
+}
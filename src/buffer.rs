@@ -0,0 +1,716 @@
+pub mod history;
+pub mod list;
+
+use std::fs;
+use std::io::prelude::*;
+use std::ops::{Range, RangeInclusive};
+
+use regex::Regex;
+use ropey::Rope;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::motion::first_non_blank;
+use crate::operator::Region;
+use crate::register::Register;
+use crate::{Shape, Window};
+use history::{Edit, History};
+
+pub const TAB_STOP: usize = 8;
+
+// the display column after drawing `grapheme` at column `col`, a tab runs on to the next tab stop
+pub fn advance(col: usize, grapheme: &str) -> usize {
+    if grapheme == "\t" {
+        return (col / TAB_STOP + 1) * TAB_STOP;
+    }
+    return col + grapheme.width();
+}
+
+#[derive(Debug)]
+pub struct Buffer {
+    pub buf_name: String,
+    text: Rope,
+    // None for a buffer that was never a file, like the :grep results
+    fd: Option<fs::File>,
+    history: History,
+    changes: Vec<TextChange>,
+    modified: bool,
+}
+
+// a single edit in the byte and (row, byte column) terms incremental parsers expect
+#[derive(Debug, Clone, Copy)]
+pub struct TextChange {
+    pub start_byte: usize,
+    pub old_end_byte: usize,
+    pub new_end_byte: usize,
+    pub start: (usize, usize),
+    pub old_end: (usize, usize),
+    pub new_end: (usize, usize),
+}
+
+impl Buffer {
+    pub fn new(filepath: &str) -> Result<Buffer, String> {
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(filepath);
+
+        let mut file = match file {
+            Ok(file) => file,
+            Err(_) => return Err(format!("Cant open {}, make shure you have permision to read/write", filepath)),
+        };
+
+        let mut contents = String::new();
+        if file.read_to_string(&mut contents).is_err() {
+            return Err(format!("Cant read {}, make shure it is valid text", filepath));
+        }
+
+        // the last line is terminated on write, so the rope never holds the final newline
+        let mut contents = contents.replace("\r\n", "\n");
+        if contents.ends_with('\n') {
+            contents.pop();
+        }
+
+        let text = Rope::from_str(&contents);
+        let history = History::load(filepath, &text).unwrap_or_default();
+
+        return Ok(Buffer {
+            buf_name: String::from(filepath),
+            text,
+            fd: Some(file),
+            history,
+            changes: Vec::new(),
+            modified: false,
+        });
+    }
+
+    // a buffer of generated text that has no file to be written to
+    pub fn scratch(name: &str, text: &str) -> Buffer {
+        return Buffer {
+            buf_name: String::from(name),
+            text: Rope::from_str(text),
+            fd: None,
+            history: History::default(),
+            changes: Vec::new(),
+            modified: false,
+        };
+    }
+
+    pub fn is_scratch(&self) -> bool {
+        return self.fd.is_none();
+    }
+
+    pub fn is_modified(&self) -> bool {
+        return self.modified;
+    }
+
+    pub fn len_lines(&self) -> usize {
+        return self.text.len_lines();
+    }
+
+    pub fn line(&self, row: usize) -> String {
+        let mut line = self.text.line(row).to_string();
+        if line.ends_with('\n') {
+            line.pop();
+        }
+        return line;
+    }
+
+    pub fn line_len(&self, row: usize) -> usize {
+        let line = self.text.line(row);
+        let len = line.len_chars();
+        if len != 0 && line.char(len - 1) == '\n' {
+            return len - 1;
+        }
+        return len;
+    }
+
+    // converts a char column into a byte offset within `line`
+    fn byte_col(line: &str, col: usize) -> usize {
+        return line.char_indices().nth(col).map_or(line.len(), |(byte, _)| byte);
+    }
+
+    pub fn next_grapheme(&self, row: usize, col: usize) -> usize {
+        let line = self.line(row);
+        match line[Self::byte_col(&line, col)..].graphemes(true).next() {
+            Some(grapheme) => col + grapheme.chars().count(),
+            None => col,
+        }
+    }
+
+    pub fn prev_grapheme(&self, row: usize, col: usize) -> usize {
+        let line = self.line(row);
+        match line[..Self::byte_col(&line, col)].graphemes(true).next_back() {
+            Some(grapheme) => col - grapheme.chars().count(),
+            None => col,
+        }
+    }
+
+    // snaps a column that points into the middle of a grapheme cluster back to its start
+    pub fn grapheme_start(&self, row: usize, col: usize) -> usize {
+        let line = self.line(row);
+        let byte = Self::byte_col(&line, col);
+        let mut start = 0;
+        for (offset, grapheme) in line.grapheme_indices(true) {
+            if offset + grapheme.len() > byte {
+                return line[..offset].chars().count();
+            }
+            start += grapheme.chars().count();
+        }
+        return start;
+    }
+
+    pub fn display_col(&self, row: usize, col: usize) -> usize {
+        let line = self.line(row);
+        return line[..Self::byte_col(&line, col)].graphemes(true).fold(0, advance);
+    }
+
+    // the column of the grapheme drawn over display column `width`, or the end of a line too short for it
+    pub fn col_at_width(&self, row: usize, width: usize) -> usize {
+        let (mut col, mut used) = (0, 0);
+        for grapheme in self.line(row).graphemes(true) {
+            used = advance(used, grapheme);
+            if used > width {
+                return col;
+            }
+            col += grapheme.chars().count();
+        }
+        return col;
+    }
+
+    pub fn char_idx(&self, row: i32, col: i32) -> usize {
+        return self.text.line_to_char(row as usize) + col as usize;
+    }
+
+    pub fn slice(&self, range: Range<usize>) -> String {
+        return self.text.slice(range).to_string();
+    }
+
+    fn idx_pos(&self, idx: usize) -> (i32, i32) {
+        let row = self.text.char_to_line(idx);
+        return (row as i32, (idx - self.text.line_to_char(row)) as i32);
+    }
+
+    pub fn rope(&self) -> &Rope {
+        return &self.text;
+    }
+
+    // byte offset of a char index together with its (row, byte column) point
+    fn byte_point(&self, idx: usize) -> (usize, (usize, usize)) {
+        let byte = self.text.char_to_byte(idx);
+        let row = self.text.char_to_line(idx);
+        return (byte, (row, byte - self.text.line_to_byte(row)));
+    }
+
+    // every change to the rope goes through here so highlighters can catch up with take_changes
+    fn apply(&mut self, edit: &Edit) {
+        let (start_byte, start) = self.byte_point(edit.at());
+        self.modified = true;
+        match edit {
+            Edit::Insert { at, text } => {
+                self.text.insert(*at, text);
+                let (new_end_byte, new_end) = self.byte_point(at + text.chars().count());
+                self.changes.push(TextChange { start_byte, old_end_byte: start_byte, new_end_byte, start, old_end: start, new_end });
+            },
+            Edit::Remove { at, text } => {
+                let (old_end_byte, old_end) = self.byte_point(at + text.chars().count());
+                self.text.remove(*at..at + text.chars().count());
+                self.changes.push(TextChange { start_byte, old_end_byte, new_end_byte: start_byte, start, old_end, new_end: start });
+            },
+        }
+    }
+
+    pub fn take_changes(&mut self) -> Vec<TextChange> {
+        return std::mem::take(&mut self.changes);
+    }
+
+    pub fn insert_text(&mut self, idx: usize, text: &str) {
+        let edit = Edit::Insert { at: idx, text: text.to_string() };
+        self.apply(&edit);
+        self.history.record(edit, self.idx_pos(idx));
+    }
+
+    pub fn remove_text(&mut self, range: Range<usize>) {
+        let edit = Edit::Remove { at: range.start, text: self.slice(range.clone()) };
+        self.apply(&edit);
+        self.history.record(edit, self.idx_pos(range.start));
+    }
+
+    // every edit between begin_change and end_change is undone as one step
+    pub fn begin_change(&mut self, window: &Window) {
+        self.history.begin((window.cursor_row, window.cursor_col));
+    }
+
+    pub fn end_change(&mut self) {
+        self.history.end();
+    }
+
+    pub fn undo(&mut self, window: &mut Window) -> bool {
+        let change = match self.history.undo.pop() {
+            Some(change) => change,
+            None => return false,
+        };
+
+        for edit in change.edits.iter().rev() {
+            self.apply(&edit.inverse());
+        }
+        window.restore_cursor(self, change.cursor);
+        self.history.redo.push(change);
+        return true;
+    }
+
+    pub fn redo(&mut self, window: &mut Window) -> bool {
+        let change = match self.history.redo.pop() {
+            Some(change) => change,
+            None => return false,
+        };
+
+        for edit in &change.edits {
+            self.apply(edit);
+        }
+        window.restore_cursor(self, change.cursor);
+        self.history.undo.push(change);
+        return true;
+    }
+
+    pub fn insert(&mut self, window: &Window, character: char) {
+        let idx = self.char_idx(window.cursor_row, window.cursor_col);
+        self.begin_change(window);
+        self.insert_text(idx, &character.to_string());
+        self.end_change();
+    }
+
+    // text put in as it is, without the indentation newline adds, leaving the cursor after it
+    pub fn insert_str(&mut self, window: &mut Window, text: &str) {
+        let idx = self.char_idx(window.cursor_row, window.cursor_col);
+        self.begin_change(window);
+        self.insert_text(idx, text);
+        self.end_change();
+        window.restore_cursor(self, self.idx_pos(idx + text.chars().count()));
+    }
+
+    // the chars a region covers, a linewise region takes the newline after its last line,
+    // or the one before its first line when it runs to the end of the buffer
+    pub fn region_range(&self, region: &Region) -> Range<usize> {
+        if !region.linewise {
+            return self.char_idx(region.start.0 as i32, region.start.1 as i32)..self.char_idx(region.end.0 as i32, region.end.1 as i32);
+        }
+
+        let start = self.text.line_to_char(region.start.0);
+        if region.end.0 + 1 < self.len_lines() {
+            return start..self.text.line_to_char(region.end.0 + 1);
+        } else if start != 0 {
+            return start - 1..self.text.len_chars();
+        }
+        return start..self.text.len_chars();
+    }
+
+    // what an operator yanks, linewise text comes without the newline after the last line
+    pub fn region_text(&self, region: &Region) -> String {
+        if region.linewise {
+            return (region.start.0..=region.end.0).map(|row| self.line(row)).collect::<Vec<String>>().join("\n");
+        }
+        return self.slice(self.region_range(region));
+    }
+
+    // the same lines without their newlines, as the charwise region they would be
+    fn line_region(&self, region: &Region) -> Region {
+        if !region.linewise {
+            return *region;
+        }
+        return Region {
+            start: (region.start.0, 0),
+            end: (region.end.0, self.line_len(region.end.0)),
+            linewise: false,
+        };
+    }
+
+    pub fn delete_region(&mut self, window: &mut Window, region: &Region) -> String {
+        let text = self.region_text(region);
+        let range = self.region_range(region);
+        if !range.is_empty() {
+            self.begin_change(window);
+            self.remove_text(range);
+            self.end_change();
+        }
+
+        if region.linewise {
+            let row = region.start.0.min(self.len_lines() - 1);
+            window.restore_cursor(self, (row as i32, first_non_blank(self, row) as i32));
+        } else {
+            window.restore_cursor(self, (region.start.0 as i32, region.start.1 as i32));
+        }
+        return text;
+    }
+
+    // gU and gu, left alone where the case does not change so undo has nothing to do
+    pub fn map_case(&mut self, window: &mut Window, region: &Region, upper: bool) {
+        let range = self.region_range(&self.line_region(region));
+        let text = self.slice(range.clone());
+        let mapped = if upper { text.to_uppercase() } else { text.to_lowercase() };
+        if mapped != text {
+            self.begin_change(window);
+            self.remove_text(range.clone());
+            self.insert_text(range.start, &mapped);
+            self.end_change();
+        }
+        window.restore_cursor(self, (region.start.0 as i32, region.start.1 as i32));
+    }
+
+    // > and < shift every line of the region by four spaces, blank lines are not indented
+    pub fn indent_rows(&mut self, window: &mut Window, region: &Region, outdent: bool) {
+        self.begin_change(window);
+        for row in region.start.0..=region.end.0 {
+            let start = self.text.line_to_char(row);
+            if outdent {
+                let width = self.get_identation(row).min(4);
+                self.remove_text(start..start + width);
+            } else if self.line_len(row) != 0 {
+                self.insert_text(start, "    ");
+            }
+        }
+        self.end_change();
+        window.restore_cursor(self, (region.start.0 as i32, first_non_blank(self, region.start.0) as i32));
+    }
+
+    pub fn delete(&mut self, window: &mut Window) {
+        self.begin_change(window);
+        if window.cursor_col == 0 && window.cursor_row != 0 {
+            let previous_len = self.line_len(window.cursor_row as usize - 1);
+            let idx = self.char_idx(window.cursor_row, 0);
+            self.remove_text(idx - 1..idx);
+            window.restore_cursor(self, (window.cursor_row - 1, previous_len as i32));
+        } else if window.cursor_col != 0 {
+            // the cursor goes to where the grapheme started, the text after it has moved up
+            let col = self.prev_grapheme(window.cursor_row as usize, window.cursor_col as usize) as i32;
+            let idx = self.char_idx(window.cursor_row, window.cursor_col);
+            self.remove_text(self.char_idx(window.cursor_row, col)..idx);
+            window.cursor_col = col;
+        }
+        self.end_change();
+    }
+
+    pub fn get_identation(&self, line: usize) -> usize {
+        for (counter, character) in self.text.line(line).chars().enumerate() {
+            if character != ' ' {
+                return counter;
+            }
+        }
+        return 0;
+    }
+
+    pub fn newline_down(&mut self, window: &mut Window) {
+        let idx = self.char_idx(window.cursor_row, self.line_len(window.cursor_row as usize) as i32);
+        self.begin_change(window);
+        self.insert_text(idx, "\n");
+        self.end_change();
+        window.restore_cursor(self, (window.cursor_row + 1, 0));
+    }
+
+    pub fn newline(&mut self, window: &mut Window) {
+        let identation_count = self.get_identation(window.cursor_row as usize);
+        let idx = self.char_idx(window.cursor_row, window.cursor_col);
+        self.begin_change(window);
+        self.insert_text(idx, &format!("\n{}", " ".repeat(identation_count)));
+        self.end_change();
+        window.restore_cursor(self, (window.cursor_row + 1, identation_count as i32));
+    }
+
+    // the columns `cols` of every row in `rows`, rows too short for the block give what they have
+    pub fn block_text(&self, rows: RangeInclusive<usize>, cols: Range<usize>) -> String {
+        let lines = rows.map(|row| {
+            let line = self.line(row).chars().collect::<Vec<char>>();
+            return line[cols.start.min(line.len())..cols.end.min(line.len())].iter().collect::<String>();
+        });
+        return lines.collect::<Vec<String>>().join("\n");
+    }
+
+    pub fn delete_block(&mut self, window: &mut Window, rows: RangeInclusive<usize>, cols: Range<usize>) -> String {
+        let text = self.block_text(rows.clone(), cols.clone());
+        self.begin_change(window);
+        for row in rows.clone() {
+            let len = self.line_len(row);
+            if cols.start < len {
+                self.remove_text(self.char_idx(row as i32, cols.start as i32)..self.char_idx(row as i32, cols.end.min(len) as i32));
+            }
+        }
+        self.end_change();
+        window.restore_cursor(self, (*rows.start() as i32, cols.start as i32));
+        return text;
+    }
+
+    // repeats what was typed on the first row of a block insert on the rest of it, rows that end
+    // before `col` are padded with spaces when `pad` is set and skipped otherwise
+    pub fn insert_block(&mut self, rows: RangeInclusive<usize>, col: usize, text: &str, pad: bool) {
+        for row in rows {
+            let len = self.line_len(row);
+            if len < col && !pad {
+                continue;
+            }
+            let padded = " ".repeat(col.saturating_sub(len)) + text;
+            if !padded.is_empty() {
+                let idx = self.char_idx(row as i32, len.min(col) as i32);
+                self.insert_text(idx, &padded);
+            }
+        }
+    }
+
+    // p puts charwise text after the cursor, lines below it and a block one column to the right,
+    // P puts them before, above and at the cursor
+    pub fn paste(&mut self, window: &mut Window, register: &Register, before: bool, count: usize) {
+        let (row, col) = (window.cursor_row as usize, window.cursor_col as usize);
+        if register.text.is_empty() && register.shape != Shape::Line {
+            return;
+        }
+        self.begin_change(window);
+        match register.shape {
+            Shape::Char => {
+                let col = if before { col } else { self.next_grapheme(row, col) };
+                let text = register.text.repeat(count);
+                self.insert_text(self.char_idx(row as i32, col as i32), &text);
+
+                // the cursor ends on the last char of a single line paste, at the start of a longer one
+                let end = if text.contains('\n') { col } else { col + text.chars().count() };
+                window.restore_cursor(self, (row as i32, self.grapheme_start(row, end.saturating_sub(1).max(col)) as i32));
+            },
+            Shape::Line => {
+                let lines = vec![register.text.as_str(); count].join("\n");
+                let first = if before { row } else { row + 1 };
+                if before {
+                    self.insert_text(self.text.line_to_char(row), &(lines + "\n"));
+                } else {
+                    self.insert_text(self.char_idx(row as i32, self.line_len(row) as i32), &("\n".to_string() + &lines));
+                }
+                window.restore_cursor(self, (first as i32, first_non_blank(self, first) as i32));
+            },
+            Shape::Block => {
+                let col = if before || self.line_len(row) == 0 { col } else { self.next_grapheme(row, col) };
+                for (offset, line) in register.text.split('\n').enumerate() {
+                    let target = row + offset;
+                    if target >= self.len_lines() {
+                        self.insert_text(self.text.len_chars(), "\n");
+                    }
+                    let len = self.line_len(target);
+                    let text = line.repeat(count);
+                    if !text.is_empty() {
+                        let padded = " ".repeat(col.saturating_sub(len)) + &text;
+                        self.insert_text(self.char_idx(target as i32, col.min(len) as i32), &padded);
+                    }
+                }
+                window.restore_cursor(self, (row as i32, col as i32));
+            },
+        }
+        self.end_change();
+    }
+
+    // every match of `pattern` on line `row` as a range of columns, in chars
+    pub fn find(&self, pattern: &Regex, row: usize) -> Vec<Range<usize>> {
+        let line = self.line(row);
+        return pattern
+            .find_iter(&line)
+            .map(|found| {
+                let start = line[..found.start()].chars().count();
+                return start..start + found.as_str().chars().count();
+            })
+            .collect();
+    }
+
+    // where the next match after `pos` starts, or the one before it when `backward`, and whether
+    // the search had to go round the end of the buffer to get there
+    pub fn search(&self, pattern: &Regex, (row, col): (usize, usize), backward: bool) -> Option<((usize, usize), bool)> {
+        let lines = self.len_lines();
+        // the last step comes back round to the cursor line for what is on the other side of the cursor
+        for step in 0..=lines {
+            let (current, wrapped) = if backward {
+                ((row + lines - step % lines) % lines, step > row)
+            } else {
+                ((row + step) % lines, row + step >= lines)
+            };
+            let starts = self.find(pattern, current).into_iter().map(|found| found.start).collect::<Vec<usize>>();
+            let found = match (backward, step) {
+                (false, 0) => starts.iter().find(|start| **start > col),
+                (false, _) if step == lines => starts.iter().find(|start| **start <= col),
+                (false, _) => starts.first(),
+                (true, 0) => starts.iter().rev().find(|start| **start < col),
+                (true, _) if step == lines => starts.iter().rev().find(|start| **start >= col),
+                (true, _) => starts.last(),
+            };
+            if let Some(start) = found {
+                return Some(((current, *start), wrapped));
+            }
+        }
+        return None;
+    }
+
+    pub fn handle_command(&mut self, command: String) -> String {
+        let command = command.split(' ').collect::<Vec<&str>>();
+        match command[0] {
+            ":E" => {
+                match self.write() {
+                    Ok(written) => return format!("\"{}\", {}B written", self.buf_name.clone(), written),
+                    Err(err) => return err,
+                }
+            },
+            _ => {
+                return format!("Unknown command: {}", command.join(" "));
+            },
+        }
+    }
+
+    pub fn write(&mut self) -> Result<u64, String> {
+        let fd = match &mut self.fd {
+            Some(fd) => fd,
+            None => return Err(format!("\"{}\" has no file to write to", self.buf_name)),
+        };
+        fd.set_len(0).unwrap();
+        fd.rewind().unwrap();
+
+        self.text.write_to(&mut *fd).unwrap();
+        fd.write_all(b"\n").unwrap();
+        self.modified = false;
+
+        // losing the undo file only costs history, so a failure here is not worth reporting
+        let _ = self.history.save(&self.buf_name, &self.text);
+        return Ok(fd.metadata().unwrap().len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::process;
+    use super::*;
+
+    // every test gets its own file so they can run in parallel
+    fn buffer(name: &str, contents: &str) -> Buffer {
+        let dir = env::temp_dir().join(format!("zin-test-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        return Buffer::new(&path.to_string_lossy()).unwrap();
+    }
+
+    fn window_at(row: i32, col: i32) -> Window {
+        return Window { cursor_col: col, cursor_row: row, win_row: 0, win_width: 80, win_height: 24, win_top: 0, win_left: 0, buffer: 0, desired_col: None };
+    }
+
+    // what dd deletes
+    fn line(row: usize) -> Region {
+        return Region { start: (row, 0), end: (row, 0), linewise: true };
+    }
+
+    fn lines(buffer: &Buffer) -> Vec<String> {
+        return (0..buffer.len_lines()).map(|row| buffer.line(row)).collect();
+    }
+
+    #[test]
+    fn edits_go_by_char_range() {
+        let mut buffer = buffer("rope.txt", "one\ntwö\r\nthree\n");
+        assert_eq!(lines(&buffer), ["one", "twö", "three"]);
+        assert_eq!(buffer.line_len(1), 3);
+
+        buffer.insert_text(buffer.char_idx(1, 3), "!\nnew");
+        assert_eq!(lines(&buffer), ["one", "twö!", "new", "three"]);
+        assert_eq!(buffer.slice(buffer.char_idx(1, 2)..buffer.char_idx(2, 1)), "ö!\nn");
+
+        // a range over a line break joins the lines on either side of it
+        buffer.remove_text(buffer.char_idx(0, 1)..buffer.char_idx(2, 0));
+        assert_eq!(lines(&buffer), ["onew", "three"]);
+
+        // the final newline is put back on write
+        assert_eq!(buffer.write(), Ok(11));
+        assert_eq!(fs::read_to_string(&buffer.buf_name).unwrap(), "onew\nthree\n");
+    }
+
+    #[test]
+    fn lines_are_deleted_and_pasted() {
+        let mut buffer = buffer("lines.txt", "one\ntwo\nthree\n");
+        let mut window = window_at(2, 0);
+        buffer.delete_region(&mut window, &line(2));
+        assert_eq!(lines(&buffer), ["one", "two"]);
+        assert_eq!(window.cursor_row, 1);
+
+        buffer.paste(&mut window, &Register::new(String::from("three"), Shape::Line), true, 1);
+        assert_eq!(lines(&buffer), ["one", "three", "two"]);
+        assert_eq!(buffer.find(&Regex::new("t").unwrap(), 2), vec![0..1]);
+        assert!(buffer.find(&Regex::new("x").unwrap(), 1).is_empty());
+    }
+
+    #[test]
+    fn columns_step_over_whole_graphemes() {
+        let mut buffer = buffer("graphemes.txt", "日本語\ne\u{301}a\u{301}b\n");
+        assert_eq!(buffer.next_grapheme(0, 1), 2);
+        assert_eq!(buffer.display_col(0, 2), 4);
+
+        // an accent typed as its own char is part of the letter before it
+        assert_eq!(buffer.next_grapheme(1, 0), 2);
+        assert_eq!(buffer.prev_grapheme(1, 4), 2);
+        assert_eq!(buffer.grapheme_start(1, 3), 2);
+        assert_eq!(buffer.display_col(1, 4), 2);
+        assert_eq!(buffer.next_grapheme(1, 5), 5);
+
+        let mut window = window_at(1, 4);
+        buffer.delete(&mut window);
+        assert_eq!(buffer.line(1), "e\u{301}b");
+        assert_eq!(window.cursor_col, 2);
+
+        buffer.insert(&window, 'ü');
+        assert_eq!(buffer.line(1), "e\u{301}üb");
+    }
+
+    #[test]
+    fn tabs_run_to_the_next_tab_stop() {
+        let buffer = buffer("tabs.txt", "\tab\nxyz\tw\n");
+        assert_eq!(buffer.display_col(0, 1), 8);
+        assert_eq!(buffer.display_col(1, 4), 8);
+        // a column inside the tab's cells lands on the tab
+        assert_eq!(buffer.col_at_width(0, 5), 0);
+        assert_eq!(buffer.col_at_width(0, 8), 1);
+        assert_eq!(buffer.col_at_width(1, 6), 3);
+    }
+
+    #[test]
+    fn undo_steps_restore_the_cursor_and_redo_them() {
+        let mut buffer = buffer("undo.txt", "one\ntwo\n");
+        let mut window = window_at(1, 2);
+        // an insert session is one step however much was typed in it
+        buffer.begin_change(&window);
+        buffer.insert(&window, 'a');
+        window.cursor_col += 1;
+        buffer.delete(&mut window);
+        buffer.insert(&window, 'b');
+        window.cursor_col += 1;
+        buffer.newline(&mut window);
+        buffer.insert(&window, 'c');
+        buffer.end_change();
+        buffer.delete_region(&mut window_at(0, 0), &line(0));
+        assert_eq!(lines(&buffer), ["twb", "co"]);
+
+        let mut window = window_at(1, 1);
+        assert!(buffer.undo(&mut window));
+        assert_eq!(lines(&buffer), ["one", "twb", "co"]);
+        assert_eq!((window.cursor_row, window.cursor_col), (0, 0));
+        assert!(buffer.undo(&mut window));
+        assert_eq!(lines(&buffer), ["one", "two"]);
+        assert_eq!((window.cursor_row, window.cursor_col), (1, 2));
+        assert!(!buffer.undo(&mut window));
+
+        assert!(buffer.redo(&mut window));
+        assert!(buffer.redo(&mut window));
+        assert_eq!(lines(&buffer), ["twb", "co"]);
+        assert!(!buffer.redo(&mut window));
+    }
+
+    #[test]
+    fn large_files_stay_editable() {
+        let contents = (0..200_000).map(|row| format!("line {}\n", row)).collect::<String>();
+        let mut buffer = buffer("large.txt", &contents);
+        buffer.delete_region(&mut window_at(199_999, 0), &line(199_999));
+        buffer.delete_region(&mut window_at(0, 0), &line(0));
+        assert_eq!(buffer.len_lines(), 199_998);
+        assert_eq!(buffer.line(0), "line 1");
+        assert_eq!(buffer.line(199_997), "line 199998");
+    }
+}
@@ -0,0 +1,415 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use ropey::Rope;
+
+#[derive(Debug, Clone)]
+pub enum Edit {
+    Insert { at: usize, text: String },
+    Remove { at: usize, text: String },
+}
+
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub edits: Vec<Edit>,
+    pub cursor: (i32, i32),
+}
+
+#[derive(Debug, Default)]
+pub struct History {
+    pub undo: Vec<Change>,
+    pub redo: Vec<Change>,
+    group: Option<Change>,
+    depth: usize,
+}
+
+impl Edit {
+    pub fn at(&self) -> usize {
+        match self {
+            Edit::Insert { at, .. } | Edit::Remove { at, .. } => return *at,
+        }
+    }
+
+    pub fn inverse(&self) -> Edit {
+        match self {
+            Edit::Insert { at, text } => return Edit::Remove { at: *at, text: text.clone() },
+            Edit::Remove { at, text } => return Edit::Insert { at: *at, text: text.clone() },
+        }
+    }
+
+    // folds typing and backspacing runs into a single edit so a session stays compact
+    fn merge(&mut self, next: &Edit) -> bool {
+        match (self, next) {
+            (Edit::Insert { at, text }, Edit::Insert { at: next_at, text: next_text })
+                if *at + text.chars().count() == *next_at => {
+                text.push_str(next_text);
+                return true;
+            },
+            (Edit::Remove { at, text }, Edit::Remove { at: next_at, text: next_text })
+                if *next_at + next_text.chars().count() == *at => {
+                *at = *next_at;
+                text.insert_str(0, next_text);
+                return true;
+            },
+            (Edit::Remove { at, text }, Edit::Remove { at: next_at, text: next_text }) if *next_at == *at => {
+                text.push_str(next_text);
+                return true;
+            },
+            _ => return false,
+        }
+    }
+}
+
+impl History {
+    pub fn new() -> History {
+        return History {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            group: None,
+            depth: 0,
+        };
+    }
+
+    // groups nest, only the outermost one becomes an undo step
+    pub fn begin(&mut self, cursor: (i32, i32)) {
+        if self.depth == 0 {
+            self.group = Some(Change {
+                edits: Vec::new(),
+                cursor,
+            });
+        }
+        self.depth += 1;
+    }
+
+    pub fn end(&mut self) {
+        if self.depth == 0 {
+            return;
+        }
+
+        self.depth -= 1;
+        if self.depth == 0 {
+            if let Some(change) = self.group.take() {
+                self.commit(change);
+            }
+        }
+    }
+
+    pub fn record(&mut self, edit: Edit, cursor: (i32, i32)) {
+        match &mut self.group {
+            Some(change) => {
+                let merged = match change.edits.last_mut() {
+                    Some(last) => last.merge(&edit),
+                    None => false,
+                };
+                if !merged {
+                    change.edits.push(edit);
+                }
+            },
+            None => {
+                self.commit(Change {
+                    edits: vec![edit],
+                    cursor,
+                });
+            },
+        }
+    }
+
+    fn commit(&mut self, change: Change) {
+        if !change.edits.is_empty() {
+            self.undo.push(change);
+            self.redo.clear();
+        }
+    }
+}
+
+// FNV-1a, stable across builds unlike the std hasher
+fn fnv1a(bytes: impl Iterator<Item = u8>, mut hash: u64) -> u64 {
+    for byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    return hash;
+}
+
+pub fn content_hash(text: &Rope) -> u64 {
+    let mut hash = 0xcbf29ce484222325;
+    for chunk in text.chunks() {
+        hash = fnv1a(chunk.bytes(), hash);
+    }
+    return hash;
+}
+
+// undo files live in $XDG_CACHE_HOME/zin/undo, named after a hash of the absolute path
+fn undo_path(filepath: &str) -> Option<(PathBuf, String)> {
+    let cache = match env::var_os("XDG_CACHE_HOME") {
+        Some(cache) if !cache.is_empty() => PathBuf::from(cache),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".cache"),
+    };
+    let absolute = fs::canonicalize(filepath).ok()?.to_string_lossy().to_string();
+    let name = format!("{:016x}", fnv1a(absolute.bytes(), 0xcbf29ce484222325));
+    return Some((cache.join("zin").join("undo").join(name), absolute));
+}
+
+fn write_changes(out: &mut String, name: &str, changes: &[Change]) {
+    out.push_str(&format!("{} {}\n", name, changes.len()));
+    for change in changes {
+        out.push_str(&format!("change {} {} {}\n", change.cursor.0, change.cursor.1, change.edits.len()));
+        for edit in &change.edits {
+            let (kind, at, text) = match edit {
+                Edit::Insert { at, text } => ("i", at, text),
+                Edit::Remove { at, text } => ("r", at, text),
+            };
+            out.push_str(&format!("{} {} {}\n{}\n", kind, at, text.len(), text));
+        }
+    }
+}
+
+struct Reader<'a> {
+    data: &'a str,
+}
+
+impl<'a> Reader<'a> {
+    fn line(&mut self) -> Option<Vec<&'a str>> {
+        let end = self.data.find('\n')?;
+        let line = &self.data[..end];
+        self.data = &self.data[end + 1..];
+        return Some(line.split(' ').collect());
+    }
+
+    // edit texts are length prefixed since they may contain newlines themselves
+    fn text(&mut self, len: usize) -> Option<String> {
+        let text = self.data.get(..len)?.to_string();
+        self.data = self.data.get(len..)?.strip_prefix('\n')?;
+        return Some(text);
+    }
+
+    fn changes(&mut self, name: &str) -> Option<Vec<Change>> {
+        let header = self.line()?;
+        if header.len() != 2 || header[0] != name {
+            return None;
+        }
+
+        let mut changes = Vec::new();
+        for _ in 0..header[1].parse::<usize>().ok()? {
+            let change = self.line()?;
+            if change.len() != 4 || change[0] != "change" {
+                return None;
+            }
+
+            let mut edits = Vec::new();
+            for _ in 0..change[3].parse::<usize>().ok()? {
+                let edit = self.line()?;
+                if edit.len() != 3 {
+                    return None;
+                }
+                let at = edit[1].parse().ok()?;
+                let text = self.text(edit[2].parse().ok()?)?;
+                edits.push(match edit[0] {
+                    "i" => Edit::Insert { at, text },
+                    "r" => Edit::Remove { at, text },
+                    _ => return None,
+                });
+            }
+
+            changes.push(Change {
+                edits,
+                cursor: (change[1].parse().ok()?, change[2].parse().ok()?),
+            });
+        }
+        return Some(changes);
+    }
+}
+
+// whether `changes` can be undone, or redone, one after another starting from `text`
+fn replays(text: &Rope, changes: &[Change], undo: bool) -> bool {
+    let mut text = text.clone();
+    for change in changes.iter().rev() {
+        let mut edits = change.edits.clone();
+        if undo {
+            edits.reverse();
+        }
+        for edit in edits {
+            // undoing an insert takes its text out again and undoing a removal puts it back
+            let (insert, at, edited) = match edit {
+                Edit::Insert { at, text } => (!undo, at, text),
+                Edit::Remove { at, text } => (undo, at, text),
+            };
+            let end = at + edited.chars().count();
+            if insert && at <= text.len_chars() {
+                text.insert(at, &edited);
+            } else if !insert && end <= text.len_chars() && text.slice(at..end) == edited.as_str() {
+                text.remove(at..end);
+            } else {
+                return false;
+            }
+        }
+    }
+    return true;
+}
+
+impl History {
+    pub fn save(&self, filepath: &str, text: &Rope) -> io::Result<()> {
+        let (path, absolute) = match undo_path(filepath) {
+            Some(undo) => undo,
+            None => return Err(io::Error::new(io::ErrorKind::NotFound, "no cache directory")),
+        };
+
+        let mut out = format!("zin-undo 1\npath {}\nhash {:016x}\n", absolute, content_hash(text));
+        write_changes(&mut out, "undo", &self.undo);
+        write_changes(&mut out, "redo", &self.redo);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        return fs::write(&path, out);
+    }
+
+    // anything that does not match the file as it is on disk now is thrown away
+    pub fn load(filepath: &str, text: &Rope) -> Option<History> {
+        let (path, absolute) = undo_path(filepath)?;
+        let data = fs::read_to_string(path).ok()?;
+        let mut reader = Reader { data: &data };
+
+        if reader.line()? != ["zin-undo", "1"] {
+            return None;
+        }
+        let saved_path = reader.line()?;
+        if saved_path.first() != Some(&"path") || saved_path[1..].join(" ") != absolute {
+            return None;
+        }
+        if reader.line()? != ["hash", &format!("{:016x}", content_hash(text))] {
+            return None;
+        }
+
+        let mut history = History::new();
+        history.undo = reader.changes("undo")?;
+        history.redo = reader.changes("redo")?;
+        // a damaged file can still carry the right hash, and an edit out of place would panic on undo
+        if !replays(text, &history.undo, true) || !replays(text, &history.redo, false) {
+            return None;
+        }
+        return Some(history);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process;
+    use super::*;
+
+    fn insert(at: usize, text: &str) -> Edit {
+        return Edit::Insert { at, text: text.to_string() };
+    }
+
+    fn remove(at: usize, text: &str) -> Edit {
+        return Edit::Remove { at, text: text.to_string() };
+    }
+
+    fn edits(change: &Change) -> Vec<String> {
+        return change.edits.iter().map(|edit| format!("{:?}", edit)).collect();
+    }
+
+    #[test]
+    fn typing_merges_into_one_edit() {
+        let mut history = History::new();
+        history.begin((0, 0));
+        history.record(insert(0, "a"), (0, 0));
+        history.record(insert(1, "bc"), (0, 1));
+        // backspace, then delete at the same spot
+        history.record(remove(2, "c"), (0, 3));
+        history.record(remove(1, "b"), (0, 2));
+        history.record(remove(1, "d"), (0, 1));
+        history.record(insert(5, "e"), (0, 1));
+        history.end();
+
+        assert_eq!(history.undo.len(), 1);
+        assert_eq!(history.undo[0].cursor, (0, 0));
+        assert_eq!(edits(&history.undo[0]), [
+            format!("{:?}", insert(0, "abc")),
+            format!("{:?}", remove(1, "bcd")),
+            format!("{:?}", insert(5, "e")),
+        ]);
+    }
+
+    #[test]
+    fn only_the_outer_group_is_a_step() {
+        let mut history = History::new();
+        history.begin((0, 0));
+        history.record(insert(0, "a"), (0, 0));
+        history.begin((0, 1));
+        history.record(insert(4, "b"), (0, 1));
+        history.end();
+        assert!(history.undo.is_empty());
+        history.end();
+        assert_eq!(history.undo.len(), 1);
+        assert_eq!(history.undo[0].edits.len(), 2);
+
+        // a group with nothing in it leaves no step behind and a stray end is ignored
+        history.begin((1, 0));
+        history.end();
+        history.end();
+        assert_eq!(history.undo.len(), 1);
+
+        // an edit outside any group is a step of its own and a new step drops what could be redone
+        history.redo.push(history.undo.pop().unwrap());
+        history.record(remove(0, "x"), (0, 0));
+        assert_eq!(history.undo.len(), 1);
+        assert!(history.redo.is_empty());
+    }
+
+    // every test here uses the same cache directory, so setting it from all of them is harmless
+    fn saved(name: &str, text: &Rope, history: &History) -> String {
+        let dir = env::temp_dir().join(format!("zin-test-{}", process::id()));
+        env::set_var("XDG_CACHE_HOME", dir.join("cache"));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name).to_string_lossy().to_string();
+        fs::write(&path, format!("{}\n", text)).unwrap();
+        history.save(&path, text).unwrap();
+        return path;
+    }
+
+    // "hello" with its first char deleted and a ! that was typed and undone again
+    fn edited() -> (Rope, History) {
+        let mut history = History::new();
+        history.record(remove(0, "h"), (0, 0));
+        history.redo.push(Change { edits: vec![insert(4, "!")], cursor: (0, 4) });
+        return (Rope::from_str("ello"), history);
+    }
+
+    #[test]
+    fn history_survives_a_round_trip() {
+        let (text, history) = edited();
+        let path = saved("roundtrip.txt", &text, &history);
+        let loaded = History::load(&path, &text).unwrap();
+        assert_eq!(loaded.undo.len(), 1);
+        assert_eq!(edits(&loaded.undo[0]), edits(&history.undo[0]));
+        assert_eq!(loaded.redo[0].cursor, (0, 4));
+        assert_eq!(edits(&loaded.redo[0]), edits(&history.redo[0]));
+
+        // the file changed since the history was saved
+        assert!(History::load(&path, &Rope::from_str("changed elsewhere")).is_none());
+    }
+
+    #[test]
+    fn damaged_files_are_ignored() {
+        let (text, history) = edited();
+        let path = saved("damaged.txt", &text, &history);
+        let undo = undo_path(&path).unwrap().0;
+        let saved = fs::read_to_string(&undo).unwrap();
+
+        for len in 0..saved.len() {
+            fs::write(&undo, &saved[..len]).unwrap();
+            assert!(History::load(&path, &text).is_none(), "undo file cut after {} bytes", len);
+        }
+
+        // the hash still matches, but the edits point past the end of the text
+        fs::write(&undo, saved.replace("\nr 0 1\n", "\nr 99 1\n")).unwrap();
+        assert!(History::load(&path, &text).is_none());
+        fs::write(&undo, saved.replace("\ni 4 1\n", "\ni 9 1\n")).unwrap();
+        assert!(History::load(&path, &text).is_none());
+        fs::write(&undo, saved).unwrap();
+        assert!(History::load(&path, &text).is_some());
+    }
+}
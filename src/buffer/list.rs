@@ -0,0 +1,292 @@
+use std::fs;
+
+use crate::buffer::Buffer;
+use crate::syntax::{Registry, Syntax};
+use crate::Window;
+
+// a buffer together with everything that has to survive while another one is shown
+#[derive(Debug)]
+pub struct Entry {
+    pub buffer: Buffer,
+    pub syntax: Syntax,
+    cursor: (i32, i32),
+    win_row: i32,
+}
+
+// windows refer to their buffer by its index in here
+#[derive(Debug)]
+pub struct BufferList {
+    entries: Vec<Entry>,
+}
+
+impl Entry {
+    pub fn open(filepath: &str, syntaxes: &Registry) -> Result<Entry, String> {
+        let buffer = Buffer::new(filepath)?;
+        let syntax = syntaxes.select(&buffer.buf_name, &buffer.line(0));
+        return Ok(Entry {
+            buffer,
+            syntax,
+            cursor: (0, 0),
+            win_row: 0,
+        });
+    }
+
+    fn scratch(name: &str, text: &str) -> Entry {
+        return Entry {
+            buffer: Buffer::scratch(name, text),
+            syntax: Syntax::empty(),
+            cursor: (0, 0),
+            win_row: 0,
+        };
+    }
+}
+
+fn same_file(a: &str, b: &str) -> bool {
+    return match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    };
+}
+
+impl BufferList {
+    pub fn new(entries: Vec<Entry>) -> BufferList {
+        return BufferList {
+            entries,
+        };
+    }
+
+    pub fn get(&self, index: usize) -> &Entry {
+        return &self.entries[index];
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> &mut Entry {
+        return &mut self.entries[index];
+    }
+
+    // finds the buffer already holding `filepath` or loads it
+    pub fn open(&mut self, filepath: &str, syntaxes: &Registry) -> Result<usize, String> {
+        if let Some(index) = self.entries.iter().position(|entry| same_file(&entry.buffer.buf_name, filepath)) {
+            return Ok(index);
+        }
+        self.entries.push(Entry::open(filepath, syntaxes)?);
+        return Ok(self.entries.len() - 1);
+    }
+
+    // puts `text` in the scratch buffer called `name`, which is made when there is none yet
+    pub fn scratch(&mut self, name: &str, text: &str) -> usize {
+        let entry = Entry::scratch(name, text);
+        match self.entries.iter().position(|entry| entry.buffer.is_scratch() && entry.buffer.buf_name == name) {
+            Some(index) => {
+                self.entries[index] = entry;
+                return index;
+            },
+            None => {
+                self.entries.push(entry);
+                return self.entries.len() - 1;
+            },
+        }
+    }
+
+    // a window only holds the cursor of the buffer it shows, the buffer keeps it for the next one
+    pub fn switch(&mut self, window: &mut Window, index: usize) {
+        let entry = &mut self.entries[window.buffer];
+        entry.cursor = (window.cursor_row, window.cursor_col);
+        entry.win_row = window.win_row;
+
+        window.buffer = index;
+        let entry = &self.entries[index];
+        window.win_row = entry.win_row;
+        window.restore_cursor(&entry.buffer, entry.cursor);
+    }
+
+    fn edit(&mut self, window: &mut Window, filepath: &str, syntaxes: &Registry) -> String {
+        match self.open(filepath, syntaxes) {
+            Ok(index) => {
+                self.switch(window, index);
+                return format!("\"{}\" {}L", filepath, self.entries[index].buffer.len_lines());
+            },
+            Err(err) => return err,
+        }
+    }
+
+    // every window showing the deleted buffer moves on to the next one
+    fn delete(&mut self, windows: &mut [Window], focused: usize, force: bool) -> String {
+        let index = windows[focused].buffer;
+        if self.entries.len() == 1 {
+            return String::from("Cannot delete the last buffer");
+        }
+        if !force && self.entries[index].buffer.is_modified() {
+            return String::from("No write since last change (add ! to override)");
+        }
+
+        let removed = self.entries.remove(index);
+        for window in windows.iter_mut() {
+            if window.buffer == index {
+                window.buffer = index.min(self.entries.len() - 1);
+                let entry = &self.entries[window.buffer];
+                window.win_row = entry.win_row;
+                window.restore_cursor(&entry.buffer, entry.cursor);
+            } else if window.buffer > index {
+                window.buffer -= 1;
+            }
+        }
+        return format!("Deleted \"{}\"", removed.buffer.buf_name);
+    }
+
+    // what :ls shows, one buffer a line with % on the one in the window
+    fn list(&self, current: usize) -> String {
+        let mut listed = Vec::new();
+        for (index, entry) in self.entries.iter().enumerate() {
+            let current = if index == current { "%" } else { " " };
+            let modified = if entry.buffer.is_modified() { " +" } else { "" };
+            listed.push(format!("{}{} \"{}\"{}", index + 1, current, entry.buffer.buf_name, modified));
+        }
+        return listed.join("\n");
+    }
+
+    // buffer list commands are handled here, anything else goes to the buffer on screen
+    pub fn handle_command(
+        &mut self,
+        windows: &mut [Window],
+        focused: usize,
+        command: String,
+        syntaxes: &Registry,
+    ) -> String {
+        let window = &mut windows[focused];
+        let current = window.buffer;
+        let args = command.split(' ').collect::<Vec<&str>>();
+        match args[0] {
+            ":e" => {
+                if args.len() < 2 || args[1].is_empty() {
+                    return String::from("Usage: :e <path>");
+                }
+                return self.edit(window, &args[1..].join(" "), syntaxes);
+            },
+            ":bn" => {
+                let index = (current + 1) % self.entries.len();
+                self.switch(window, index);
+                return format!("\"{}\"", self.entries[window.buffer].buffer.buf_name);
+            },
+            ":bp" => {
+                let index = (current + self.entries.len() - 1) % self.entries.len();
+                self.switch(window, index);
+                return format!("\"{}\"", self.entries[window.buffer].buffer.buf_name);
+            },
+            ":b" => {
+                match args.get(1).and_then(|number| number.parse::<usize>().ok()) {
+                    Some(number) if number >= 1 && number <= self.entries.len() => {
+                        self.switch(window, number - 1);
+                        return format!("\"{}\"", self.entries[window.buffer].buffer.buf_name);
+                    },
+                    _ => return format!("No such buffer: {}", args[1..].join(" ")),
+                }
+            },
+            ":ls" => return self.list(current),
+            ":bd" => return self.delete(windows, focused, false),
+            ":bd!" => return self.delete(windows, focused, true),
+            _ => return self.entries[current].buffer.handle_command(command),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::process;
+    use super::*;
+
+    // every test gets its own files so they can run in parallel, each one names itself on the first line
+    fn list(names: &[&str]) -> (BufferList, Vec<String>) {
+        let syntaxes = Registry::load().unwrap();
+        let mut entries = Vec::new();
+        let mut paths = Vec::new();
+        for name in names {
+            let path = file(name);
+            entries.push(Entry::open(&path, &syntaxes).unwrap());
+            paths.push(path);
+        }
+        return (BufferList::new(entries), paths);
+    }
+
+    fn file(name: &str) -> String {
+        let dir = env::temp_dir().join(format!("zin-test-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, format!("{}\nsecond line\n", name)).unwrap();
+        return path.to_string_lossy().to_string();
+    }
+
+    fn window(buffer: usize) -> Window {
+        return Window { cursor_col: 0, cursor_row: 0, win_row: 0, win_width: 80, win_height: 24, win_top: 0, win_left: 0, buffer, desired_col: None };
+    }
+
+    // runs a command in the first window
+    fn run(list: &mut BufferList, windows: &mut [Window], command: &str) -> String {
+        return list.handle_command(windows, 0, command.to_string(), &Registry::load().unwrap());
+    }
+
+    fn shown(list: &BufferList, window: &Window) -> String {
+        return list.get(window.buffer).buffer.line(0);
+    }
+
+    #[test]
+    fn buffers_are_switched_keeping_their_cursor() {
+        let (mut list, paths) = list(&["switch-a.txt", "switch-b.txt", "switch-c.txt"]);
+        let windows = &mut [window(0)];
+
+        windows[0].cursor_row = 1;
+        windows[0].cursor_col = 1;
+        assert_eq!(run(&mut list, windows, ":bn"), format!("\"{}\"", paths[1]));
+        assert_eq!((windows[0].cursor_row, windows[0].cursor_col), (0, 0));
+        run(&mut list, windows, ":bn");
+        run(&mut list, windows, ":bn");
+        assert_eq!(shown(&list, &windows[0]), "switch-a.txt");
+        assert_eq!((windows[0].cursor_row, windows[0].cursor_col), (1, 1));
+        run(&mut list, windows, ":bp");
+        assert_eq!(shown(&list, &windows[0]), "switch-c.txt");
+        run(&mut list, windows, ":b 2");
+        assert_eq!(shown(&list, &windows[0]), "switch-b.txt");
+        assert_eq!(run(&mut list, windows, ":b 4"), "No such buffer: 4");
+        assert_eq!(shown(&list, &windows[0]), "switch-b.txt");
+
+        // :e on a file that is already open goes back to its buffer
+        let other = file("switch-d.txt");
+        assert_eq!(run(&mut list, windows, &format!(":e {}", other)), format!("\"{}\" 2L", other));
+        assert_eq!(shown(&list, &windows[0]), "switch-d.txt");
+        run(&mut list, windows, &format!(":e {}", paths[0]));
+        assert_eq!((windows[0].cursor_row, windows[0].cursor_col), (1, 1));
+        assert_eq!(
+            run(&mut list, windows, ":ls"),
+            format!("1% \"{}\"\n2  \"{}\"\n3  \"{}\"\n4  \"{}\"", paths[0], paths[1], paths[2], other)
+        );
+    }
+
+    #[test]
+    fn bd_refuses_the_last_and_modified_buffers() {
+        let (mut list, paths) = list(&["delete-a.txt", "delete-b.txt"]);
+        let windows = &mut [window(0)];
+
+        list.get_mut(0).buffer.insert_text(0, "x");
+        assert_eq!(run(&mut list, windows, ":ls"), format!("1% \"{}\" +\n2  \"{}\"", paths[0], paths[1]));
+        assert_eq!(run(&mut list, windows, ":bd"), "No write since last change (add ! to override)");
+        assert_eq!(shown(&list, &windows[0]), "xdelete-a.txt");
+        assert_eq!(run(&mut list, windows, ":bd!"), format!("Deleted \"{}\"", paths[0]));
+        assert_eq!(shown(&list, &windows[0]), "delete-b.txt");
+        assert_eq!(run(&mut list, windows, ":bd"), "Cannot delete the last buffer");
+        assert_eq!(shown(&list, &windows[0]), "delete-b.txt");
+    }
+
+    #[test]
+    fn windows_on_a_deleted_buffer_move_on() {
+        let (mut list, _) = list(&["moved-a.txt", "moved-b.txt", "moved-c.txt"]);
+        // the first two windows are on b and both go to what comes after it, the last one stays on c
+        let windows = &mut [window(1), window(1), window(2)];
+
+        run(&mut list, windows, ":bd");
+        assert_eq!(windows.iter().map(|window| window.buffer).collect::<Vec<usize>>(), vec![1, 1, 1]);
+        assert_eq!(shown(&list, &windows[0]), "moved-c.txt");
+        run(&mut list, windows, ":bp");
+        assert_eq!(shown(&list, &windows[0]), "moved-a.txt");
+        assert_eq!(shown(&list, &windows[2]), "moved-c.txt");
+    }
+}
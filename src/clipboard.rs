@@ -0,0 +1,103 @@
+use std::io::{ErrorKind, Write};
+use std::process::{Command, Stdio};
+
+use crate::config::Config;
+use crate::editor::Shape;
+use crate::register::Register;
+
+// the system clipboard behind "+ and "*, reached through the configured commands and OSC 52
+#[derive(Debug, Default)]
+pub struct Clipboard {
+    copy: Option<String>,
+    paste: Option<String>,
+    osc52: bool,
+    // pasted back when there is no paste command, or when the clipboard still holds it
+    last: Option<Register>,
+    // waiting for the next frame to reach the terminal
+    outgoing: Option<String>,
+}
+
+impl Clipboard {
+    pub fn new(configuration: &Config) -> Clipboard {
+        return Clipboard {
+            copy: configuration.clipboard_copy.clone(),
+            paste: configuration.clipboard_paste.clone(),
+            osc52: configuration.osc52,
+            last: None,
+            outgoing: None,
+        };
+    }
+
+    pub fn copy(&mut self, register: Register) -> Result<(), String> {
+        if self.osc52 {
+            self.outgoing = Some(register.text.clone());
+        }
+        self.last = Some(register.clone());
+
+        let command = match &self.copy {
+            Some(command) => command,
+            None => return Ok(()),
+        };
+        let mut child = spawn(command)?.stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::null()).spawn().map_err(|err| format!("{}: {}", command, err))?;
+        let mut text = register.text;
+        if register.shape == Shape::Line {
+            text.push('\n');
+        }
+        let mut written = Ok(());
+        if let Some(mut stdin) = child.stdin.take() {
+            // a command that exits without reading closes the pipe, its exit status says more than that does
+            match stdin.write_all(text.as_bytes()) {
+                Err(err) if err.kind() != ErrorKind::BrokenPipe => written = Err(format!("{}: {}", command, err)),
+                _ => {},
+            }
+        }
+        let status = child.wait().map_err(|err| format!("{}: {}", command, err))?;
+        if !status.success() {
+            return Err(format!("{}: exited with {}", command, status));
+        }
+        return written;
+    }
+
+    // text ending in a line break comes back as whole lines
+    pub fn paste(&mut self) -> Result<Option<Register>, String> {
+        let command = match &self.paste {
+            Some(command) => command,
+            None => return Ok(self.last.clone()),
+        };
+        let output = spawn(command)?.stdin(Stdio::null()).stderr(Stdio::null()).output().map_err(|err| format!("{}: {}", command, err))?;
+        if !output.status.success() {
+            return Err(format!("{}: exited with {}", command, output.status));
+        }
+
+        let text = String::from_utf8_lossy(&output.stdout).replace("\r\n", "\n");
+        if let Some(last) = &self.last {
+            // our own copy keeps its shape, a block would otherwise come back as lines
+            let copied = if last.shape == Shape::Line { format!("{}\n", last.text) } else { last.text.clone() };
+            if copied == text {
+                return Ok(Some(last.clone()));
+            }
+        }
+        return match text.strip_suffix('\n') {
+            Some(lines) => Ok(Some(Register::new(lines.to_string(), Shape::Line))),
+            None => Ok(Some(Register::new(text, Shape::Char))),
+        };
+    }
+
+    pub fn last(&self) -> Option<&Register> {
+        return self.last.as_ref();
+    }
+
+    // the text to send to the terminal as OSC 52, once
+    pub fn take_outgoing(&mut self) -> Option<String> {
+        return self.outgoing.take();
+    }
+}
+
+// commands are split on whitespace, the first word is the program
+fn spawn(command: &str) -> Result<Command, String> {
+    let mut words = command.split_whitespace();
+    let program = words.next().ok_or_else(|| String::from("empty clipboard command"))?;
+    let mut child = Command::new(program);
+    child.args(words);
+    return Ok(child);
+}
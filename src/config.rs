@@ -1,12 +1,23 @@
-pub mod rs;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
 
 pub struct Config {
     pub insert_mode: i32,
     pub visual_mode: i32,
+    pub visual_line: i32,
+    pub visual_block: i32,
     pub normal_mode: i32,
 
     pub yank: i32,
     pub paste: i32,
+    pub paste_before: i32,
+
+    pub undo: i32,
+    pub redo: i32,
+
+    // prefix for the window commands, like <C-w> in vim
+    pub window: i32,
 
     pub background: RgbColor,
     pub background1: RgbColor,
@@ -17,6 +28,24 @@ pub struct Config {
     pub orange: RgbColor,
     pub yellow: RgbColor,
     pub quartz: RgbColor,
+
+    pub number: RgbColor,
+    pub char_literal: RgbColor,
+    pub lifetime: RgbColor,
+    pub macro_call: RgbColor,
+    pub attribute: RgbColor,
+    pub doc_comment: RgbColor,
+
+    // background of the visual selection
+    pub selection: RgbColor,
+    // background of the matches of the last search
+    pub search: RgbColor,
+
+    // commands "+ and "* copy through and paste from, run without a shell
+    pub clipboard_copy: Option<String>,
+    pub clipboard_paste: Option<String>,
+    // also send copies to the terminal as OSC 52, which works over ssh too
+    pub osc52: bool,
 }
 
 pub struct RgbColor {
@@ -30,10 +59,18 @@ impl Config {
         return Config {
             insert_mode: 105,
             visual_mode: 118,
+            visual_line: 86,
+            visual_block: 22,
             normal_mode: 27,
 
             yank: 121,
             paste: 112,
+            paste_before: 80,
+
+            undo: 117,
+            redo: 18,
+
+            window: 23,
 
             background: RgbColor {
                 red: 24,
@@ -82,8 +119,279 @@ impl Config {
                 green: 169,
                 blue: 159,
             },
+
+            number: RgbColor {
+                red: 255,
+                green: 135,
+                blue: 95,
+            },
+
+            char_literal: RgbColor {
+                red: 115,
+                green: 201,
+                blue: 54,
+            },
+
+            lifetime: RgbColor {
+                red: 150,
+                green: 166,
+                blue: 200,
+            },
+
+            macro_call: RgbColor {
+                red: 204,
+                green: 140,
+                blue: 60,
+            },
+
+            attribute: RgbColor {
+                red: 149,
+                green: 169,
+                blue: 159,
+            },
+
+            doc_comment: RgbColor {
+                red: 134,
+                green: 166,
+                blue: 110,
+            },
+
+            selection: RgbColor {
+                red: 68,
+                green: 71,
+                blue: 90,
+            },
+
+            search: RgbColor {
+                red: 229,
+                green: 192,
+                blue: 123,
+            },
+
+            clipboard_copy: None,
+            clipboard_paste: None,
+            osc52: true,
         };
     }
 }
 
+// "<Esc>", "<C-r>" and plain characters are accepted, the result is what ncurses::getch reports
+pub fn parse_key(name: &str) -> Option<i32> {
+    let mut chars = name.chars();
+    if let (Some(character), None) = (chars.next(), chars.next()) {
+        return if character.is_ascii() { Some(character as i32) } else { None };
+    }
+
+    let inner = name.strip_prefix('<')?.strip_suffix('>')?;
+    if let Some(key) = inner.strip_prefix("C-").or_else(|| inner.strip_prefix("c-")) {
+        let character = key.chars().next()?.to_ascii_lowercase();
+        if key.len() != 1 || !character.is_ascii_lowercase() {
+            return None;
+        }
+        return Some(character as i32 - 'a' as i32 + 1);
+    }
 
+    return match inner.to_lowercase().as_str() {
+        "esc" => Some(27),
+        "enter" | "cr" => Some(10),
+        "tab" => Some(9),
+        "space" => Some(32),
+        "bs" | "backspace" => Some(ncurses::KEY_BACKSPACE),
+        "lt" => Some('<' as i32),
+        "up" => Some(ncurses::KEY_UP),
+        "down" => Some(ncurses::KEY_DOWN),
+        "left" => Some(ncurses::KEY_LEFT),
+        "right" => Some(ncurses::KEY_RIGHT),
+        _ => None,
+    };
+}
+
+impl RgbColor {
+    fn parse(&mut self, value: &toml::Value) -> Result<(), String> {
+        match value {
+            toml::Value::String(hex) => {
+                let digits = hex.strip_prefix('#').unwrap_or(hex);
+                let channel = |index: usize| i16::from_str_radix(digits.get(index..index + 2)?, 16).ok();
+                match (digits.len(), channel(0), channel(2), channel(4)) {
+                    (6, Some(red), Some(green), Some(blue)) => {
+                        *self = RgbColor { red, green, blue };
+                    },
+                    _ => return Err(format!("invalid colour \"{}\"", hex)),
+                }
+            },
+            toml::Value::Table(table) => {
+                for (name, channel) in table {
+                    let field = match name.as_str() {
+                        "red" => &mut self.red,
+                        "green" => &mut self.green,
+                        "blue" => &mut self.blue,
+                        _ => return Err(format!("unknown channel `{}`", name)),
+                    };
+                    match channel.as_integer() {
+                        Some(channel) if (0..256).contains(&channel) => *field = channel as i16,
+                        _ => return Err(format!("`{}` must be a number from 0 to 255", name)),
+                    }
+                }
+            },
+            _ => return Err(String::from("expected \"#rrggbb\" or a table of red, green and blue")),
+        }
+        return Ok(());
+    }
+}
+
+impl Config {
+    fn key_field(&mut self, name: &str) -> Option<&mut i32> {
+        return match name {
+            "insert_mode" => Some(&mut self.insert_mode),
+            "visual_mode" => Some(&mut self.visual_mode),
+            "visual_line" => Some(&mut self.visual_line),
+            "visual_block" => Some(&mut self.visual_block),
+            "normal_mode" => Some(&mut self.normal_mode),
+            "yank" => Some(&mut self.yank),
+            "paste" => Some(&mut self.paste),
+            "paste_before" => Some(&mut self.paste_before),
+            "undo" => Some(&mut self.undo),
+            "redo" => Some(&mut self.redo),
+            "window" => Some(&mut self.window),
+            _ => None,
+        };
+    }
+
+    fn color_field(&mut self, name: &str) -> Option<&mut RgbColor> {
+        return match name {
+            "background" => Some(&mut self.background),
+            "background1" => Some(&mut self.background1),
+            "foreground" => Some(&mut self.foreground),
+            "foreground1" => Some(&mut self.foreground1),
+            "green" => Some(&mut self.green),
+            "orange" => Some(&mut self.orange),
+            "yellow" => Some(&mut self.yellow),
+            "quartz" => Some(&mut self.quartz),
+            "number" => Some(&mut self.number),
+            "char_literal" => Some(&mut self.char_literal),
+            "lifetime" => Some(&mut self.lifetime),
+            "macro_call" => Some(&mut self.macro_call),
+            "attribute" => Some(&mut self.attribute),
+            "doc_comment" => Some(&mut self.doc_comment),
+            "selection" => Some(&mut self.selection),
+            "search" => Some(&mut self.search),
+            _ => None,
+        };
+    }
+
+    pub fn path() -> Option<PathBuf> {
+        let config = match env::var_os("XDG_CONFIG_HOME") {
+            Some(config) if !config.is_empty() => PathBuf::from(config),
+            _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+        };
+        return Some(config.join("zin").join("config.toml"));
+    }
+
+    // the defaults from Config::init, overridden by $XDG_CONFIG_HOME/zin/config.toml when it exists
+    pub fn load() -> Result<Config, String> {
+        let mut config = Config::init();
+        let path = match Self::path() {
+            Some(path) if path.exists() => path,
+            _ => return Ok(config),
+        };
+
+        let contents = fs::read_to_string(&path)
+            .map_err(|err| format!("{}: {}", path.display(), err))?;
+        config.apply(&contents).map_err(|err| format!("{}: {}", path.display(), err))?;
+        return Ok(config);
+    }
+
+    pub fn apply(&mut self, contents: &str) -> Result<(), String> {
+        let table = contents.parse::<toml::Table>().map_err(|err| err.to_string())?;
+
+        for (section, entries) in &table {
+            let entries = match entries.as_table() {
+                Some(entries) if section == "keys" || section == "colors" || section == "clipboard" => entries,
+                Some(_) => return Err(format!("unknown section `[{}]`", section)),
+                None => return Err(format!("`{}` must be a table", section)),
+            };
+
+            for (name, value) in entries {
+                match section.as_str() {
+                    "keys" => {
+                        let key = value.as_str().and_then(parse_key);
+                        match (self.key_field(name), key) {
+                            (Some(field), Some(key)) => *field = key,
+                            (None, _) => return Err(format!("unknown key binding `keys.{}`", name)),
+                            (Some(_), None) => return Err(format!("`keys.{}`: invalid key {}", name, value)),
+                        }
+                    },
+                    "colors" => {
+                        match self.color_field(name) {
+                            Some(field) => field.parse(value).map_err(|err| format!("`colors.{}`: {}", name, err))?,
+                            None => return Err(format!("unknown colour `colors.{}`", name)),
+                        }
+                    },
+                    "clipboard" => {
+                        match (name.as_str(), value) {
+                            ("copy", toml::Value::String(command)) => self.clipboard_copy = Some(command.clone()),
+                            ("paste", toml::Value::String(command)) => self.clipboard_paste = Some(command.clone()),
+                            ("osc52", toml::Value::Boolean(enabled)) => self.osc52 = *enabled,
+                            ("copy" | "paste", _) => return Err(format!("`clipboard.{}` must be a command", name)),
+                            ("osc52", _) => return Err(String::from("`clipboard.osc52` must be true or false")),
+                            _ => return Err(format!("unknown clipboard setting `clipboard.{}`", name)),
+                        }
+                    },
+                    _ => {},
+                }
+            }
+        }
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(contents: &str) -> String {
+        return Config::init().apply(contents).unwrap_err();
+    }
+
+    #[test]
+    fn keys_are_parsed() {
+        assert_eq!(parse_key("x"), Some(120));
+        assert_eq!(parse_key("<Esc>"), Some(27));
+        assert_eq!(parse_key("<C-r>"), Some(18));
+        assert_eq!(parse_key("<c-W>"), Some(23));
+        assert_eq!(parse_key("<lt>"), Some(60));
+        assert_eq!(parse_key("é"), None);
+        assert_eq!(parse_key("<C-1>"), None);
+        assert_eq!(parse_key("<Nope>"), None);
+
+        let mut config = Config::init();
+        config.apply("[keys]\nundo = \"U\"\nredo = \"<C-y>\"\n").unwrap();
+        assert_eq!((config.undo, config.redo), (85, 25));
+    }
+
+    #[test]
+    fn colours_are_parsed() {
+        let mut config = Config::init();
+        config.apply("[colors]\nbackground = \"#10ff0a\"\ngreen = { red = 1, blue = 3 }\n").unwrap();
+        assert_eq!((config.background.red, config.background.green, config.background.blue), (16, 255, 10));
+        assert_eq!((config.green.red, config.green.blue), (1, 3));
+    }
+
+    #[test]
+    fn bad_entries_are_reported() {
+        assert_eq!(error("[keys]\nundo = \"<Nope>\"\n"), "`keys.undo`: invalid key \"<Nope>\"");
+        assert_eq!(error("[keys]\nundo = 5\n"), "`keys.undo`: invalid key 5");
+        assert_eq!(error("[keys]\nfly = \"f\"\n"), "unknown key binding `keys.fly`");
+        assert_eq!(error("[colors]\nbackground = \"#12345\"\n"), "`colors.background`: invalid colour \"#12345\"");
+        assert_eq!(error("[colors]\nbackground = \"#gg0000\"\n"), "`colors.background`: invalid colour \"#gg0000\"");
+        assert_eq!(
+            error("[colors]\nbackground = { red = 300 }\n"),
+            "`colors.background`: `red` must be a number from 0 to 255"
+        );
+        assert_eq!(error("[colors]\npink = \"#ffc0cb\"\n"), "unknown colour `colors.pink`");
+        assert_eq!(error("[colours]\n"), "unknown section `[colours]`");
+        assert_eq!(error("keys = 1\n"), "`keys` must be a table");
+        assert_eq!(error("[clipboard]\nosc52 = \"yes\"\n"), "`clipboard.osc52` must be true or false");
+        assert_eq!(error("[clipboard]\ncopy = 1\n"), "`clipboard.copy` must be a command");
+    }
+}
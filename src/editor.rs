@@ -0,0 +1,939 @@
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+use regex::{Regex, RegexBuilder};
+
+use crate::backend::Backend;
+use crate::buffer::list::{BufferList, Entry};
+use crate::buffer::Buffer;
+use crate::clipboard::Clipboard;
+use crate::config::Config;
+use crate::grep::{self, Hit, Quickfix};
+use crate::key::Key;
+use crate::layout::Layout;
+use crate::motion::{Find, Motion};
+use crate::operator::{Operator, Region};
+use crate::register::{Register, Registers};
+use crate::substitute::{self, Lines, Substitute, Substitution};
+use crate::syntax::Registry;
+use crate::textobject::TextObject;
+use crate::window::Window;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shape {
+    Char,
+    Line,
+    Block,
+}
+
+// the selection in visual mode, both ends are included and end is where the cursor is
+#[derive(Debug)]
+pub struct Visual {
+    pub start: (i32, i32),
+    pub end: (i32, i32),
+    pub shape: Shape,
+}
+
+impl Visual {
+    // the ends in buffer order
+    pub fn ordered(&self) -> ((i32, i32), (i32, i32)) {
+        if self.start <= self.end {
+            return (self.start, self.end);
+        }
+        return (self.end, self.start);
+    }
+
+    // the first and last column of a block, in chars
+    pub fn columns(&self) -> (i32, i32) {
+        return (self.start.1.min(self.end.1), self.start.1.max(self.end.1));
+    }
+
+    pub fn contains(&self, row: i32, col: i32) -> bool {
+        let (start, end) = self.ordered();
+        if row < start.0 || row > end.0 {
+            return false;
+        }
+        match self.shape {
+            Shape::Char => return (row, col) >= start && (row, col) <= end,
+            Shape::Line => return true,
+            Shape::Block => {
+                let (left, right) = self.columns();
+                return left <= col && col <= right;
+            },
+        }
+    }
+
+    pub fn label(&self) -> &str {
+        return match self.shape {
+            Shape::Char => " VISUAL ",
+            Shape::Line => " V-LINE ",
+            Shape::Block => " V-BLOCK ",
+        };
+    }
+}
+
+// the last / or ? search, which n and N repeat
+#[derive(Debug)]
+pub struct Matched {
+    pub pattern: Option<Regex>,
+    pub backward: bool,
+    // :nohlsearch hides the matches until the next search
+    pub highlight: bool,
+}
+
+#[derive(Eq, PartialEq, Debug)]
+pub enum Modes {
+    Insert,
+    Normal,
+    Visual,
+    Command,
+}
+
+// a block I, A or c, what gets typed on `row` is repeated on `rows` at <Esc>
+struct BlockInsert {
+    row: usize,
+    rows: RangeInclusive<usize>,
+    col: usize,
+    pad: bool,
+}
+
+// the whole editing engine, fed one key at a time and drawn onto any backend
+pub struct Editor {
+    configuration: Config,
+    syntaxes: Registry,
+    buffers: BufferList,
+    layout: Layout,
+    mode: Modes,
+    registers: Registers,
+    // the register named with " for the next command
+    register: Option<char>,
+    matches: Matched,
+    // cursor and scroll when / or ? was typed, the search being typed starts from there every time
+    search_start: Option<(i32, i32, i32)>,
+    // the pattern typed so far, highlighted while it is being typed
+    preview: Option<Regex>,
+    // a :s with the c flag, waiting to be told what to do with a match
+    substitution: Option<Substitution>,
+    // what :grep found, for <Enter> in its results and :cnext and :cprev
+    quickfix: Quickfix,
+    visual: Visual,
+    command: String,
+    // first key of a two key command such as gg, gU, fx, iw or <C-w>s
+    pending: Option<i32>,
+    count: Option<usize>,
+    // an operator waiting for its motion, with the count typed before it
+    operator: Option<(Operator, Option<usize>)>,
+    block_insert: Option<BlockInsert>,
+    // the last f, t, F or T for ; and , to repeat
+    last_find: Option<(Find, char)>,
+    quit: bool,
+}
+
+impl Editor {
+    // the layout starts out at 80x24 until the first resize, which run() does before anything else
+    pub fn new(configuration: Config, syntaxes: Registry, filepaths: &[String]) -> Result<Editor, String> {
+        if filepaths.is_empty() {
+            return Err(String::from("no files to open"));
+        }
+
+        let mut entries = Vec::new();
+        for filepath in filepaths {
+            entries.push(Entry::open(filepath, &syntaxes)?);
+        }
+
+        let window = Window {
+            cursor_col: 0,
+            cursor_row: 0,
+            win_row: 0,
+            win_height: 23,
+            win_width: 80,
+            win_top: 0,
+            win_left: 0,
+            buffer: 0,
+            desired_col: None,
+        };
+
+        let clipboard = Clipboard::new(&configuration);
+        return Ok(Editor {
+            configuration,
+            syntaxes,
+            buffers: BufferList::new(entries),
+            layout: Layout::new(window, 23, 80),
+            mode: Modes::Normal,
+            registers: Registers::new(clipboard),
+            register: None,
+            matches: Matched {
+                pattern: None,
+                backward: false,
+                highlight: true,
+            },
+            search_start: None,
+            preview: None,
+            substitution: None,
+            quickfix: Quickfix::default(),
+            visual: Visual {
+                start: (0, 0),
+                end: (0, 0),
+                shape: Shape::Char,
+            },
+            command: String::new(),
+            pending: None,
+            count: None,
+            operator: None,
+            block_insert: None,
+            last_find: None,
+            quit: false,
+        });
+    }
+
+    pub fn configuration(&self) -> &Config {
+        return &self.configuration;
+    }
+
+    pub fn mode(&self) -> &Modes {
+        return &self.mode;
+    }
+
+    // the message or command being typed on the bottom line
+    pub fn command_line(&self) -> &str {
+        return &self.command;
+    }
+
+    // (row, column) of the cursor in the focused window, in chars
+    pub fn cursor(&self) -> (i32, i32) {
+        let window = &self.layout.windows[self.layout.focused];
+        return (window.cursor_row, window.cursor_col);
+    }
+
+    // the buffer shown in the focused window
+    pub fn buffer(&self) -> &Buffer {
+        return &self.buffers.get(self.layout.windows[self.layout.focused].buffer).buffer;
+    }
+
+    pub fn should_quit(&self) -> bool {
+        return self.quit;
+    }
+
+    // the size of the whole screen, the last row is kept for the command line
+    pub fn resize(&mut self, rows: i32, cols: i32) {
+        self.layout.resize_screen(rows - 1, cols);
+    }
+
+    pub fn render(&mut self, backend: &mut dyn Backend) {
+        let window = self.layout.focused();
+        window.clamp_col(&self.buffers.get(window.buffer).buffer);
+        backend.set_bar_cursor(self.mode == Modes::Insert);
+        if let Some(text) = self.registers.clipboard.take_outgoing() {
+            backend.set_clipboard(&text);
+        }
+        let selection = if self.mode == Modes::Visual { Some(&self.visual) } else { None };
+        let search = if self.search_start.is_some() {
+            self.preview.as_ref()
+        } else {
+            self.matches.pattern.as_ref().filter(|_| self.matches.highlight)
+        };
+        self.layout.display(backend, &mut self.buffers, &self.mode, selection, search, &self.command);
+    }
+
+    // draws and handles keys until :q, or until the backend has no more keys to give
+    pub fn run(&mut self, backend: &mut dyn Backend) {
+        let (rows, cols) = backend.size();
+        self.resize(rows, cols);
+
+        while !self.quit {
+            self.render(backend);
+            let key = match Key::read(backend) {
+                Some(key) => key,
+                None => return,
+            };
+
+            if key == Key::Code(ncurses::KEY_RESIZE) {
+                // the backend already reports the new size by the time KEY_RESIZE arrives
+                let (rows, cols) = backend.size();
+                self.resize(rows, cols);
+                backend.clear();
+                continue;
+            }
+            self.handle_key(key);
+        }
+    }
+
+    pub fn handle_key(&mut self, key: Key) {
+        // a message of several lines stays up until the next key
+        if self.command.contains('\n') {
+            self.command.clear();
+        }
+
+        let naming = self.pending == Some(34);
+        self.dispatch(key);
+
+        // a named register is good for the one command that follows it
+        let unfinished = self.pending.is_some() || self.operator.is_some() || self.count.is_some();
+        if !naming && !unfinished && self.mode != Modes::Visual {
+            self.register = None;
+        }
+    }
+
+    fn dispatch(&mut self, key: Key) {
+        let char_code = key.code();
+        let configuration = &self.configuration;
+        let window = self.layout.focused();
+        let buffer = &mut self.buffers.get_mut(window.buffer).buffer;
+
+        // every key answers the question about the match under the cursor until the :s is over
+        if self.substitution.is_some() {
+            if let Key::Char(answer) = key {
+                self.command = self.substitute_step(Some(answer));
+            }
+            return;
+        }
+
+        // a paste drops whatever command was half typed and goes in as one edit, only insert and
+        // command mode take text, in the others it would run as keys
+        if let Key::Paste(text) = &key {
+            self.pending = None;
+            self.operator = None;
+            self.count = None;
+            match self.mode {
+                Modes::Insert => buffer.insert_str(window, text),
+                Modes::Command => {
+                    self.command.push_str(text.lines().next().unwrap_or(""));
+                    self.incremental();
+                },
+                Modes::Normal | Modes::Visual => {},
+            }
+            return;
+        }
+
+        // a count is typed before the command, 0 only adds a digit once a count has begun
+        let counting = self.mode == Modes::Normal || self.mode == Modes::Visual;
+        let digit = (49..=57).contains(&char_code) || (char_code == 48 && self.count.is_some());
+        if counting && self.pending.is_none() && digit {
+            self.count = Some(self.count.unwrap_or(0).saturating_mul(10).saturating_add((char_code - 48) as usize));
+            return;
+        }
+        let count = self.count.take();
+
+        if let Some(pending) = self.pending.take() {
+            let motion = match Find::from_key(pending) {
+                Some(kind) => match key {
+                    Key::Char(character) => {
+                        self.last_find = Some((kind, character));
+                        Some(Motion::Find { kind, character, repeated: false })
+                    },
+                    _ => None,
+                },
+                None if pending == 103 && char_code == 103 => Some(Motion::FirstLine),
+                None => None,
+            };
+
+            let operator = if pending == 103 && self.mode == Modes::Normal { Operator::from_g_key(char_code) } else { None };
+            if let Some(motion) = motion {
+                self.motion(motion, count);
+            } else if let Some(object) = TextObject::from_keys(pending, char_code) {
+                self.text_object(object, count);
+            } else if let Some(operator) = operator {
+                self.start_operator(operator, count);
+            } else if pending == configuration.window {
+                self.command = self.layout.handle_key(char_code);
+            } else if pending == 34 {
+                if let Key::Char(name) = key {
+                    self.register = Some(name).filter(|name| Registers::is_valid(*name));
+                    self.count = count;
+                }
+            } else {
+                self.operator = None;
+            }
+            return;
+        }
+
+        // ; and , repeat the last find, the comma in the other direction
+        let motion = match (char_code, self.last_find) {
+            (59, Some((kind, character))) => Some(Motion::Find { kind, character, repeated: true }),
+            (44, Some((kind, character))) => Some(Motion::Find { kind: kind.reversed(), character, repeated: true }),
+            _ => Motion::from_key(char_code),
+        };
+
+        if let Some((operator, operator_count)) = self.operator {
+            // only a motion, g, a find or the operator's own key again finish an operator, anything else cancels it
+            if char_code == operator.line_key(configuration.yank) {
+                self.operator = None;
+                let lines = multiply(operator_count, count).unwrap_or(1).max(1);
+                let first = window.cursor_row as usize;
+                let last = (first + lines - 1).min(buffer.len_lines() - 1);
+                self.apply_operator(operator, Region::lines(first, last));
+            } else if char_code == 103 || char_code == 105 || char_code == 97 || Find::from_key(char_code).is_some() {
+                self.count = count;
+                self.pending = Some(char_code);
+            } else if let Some(motion) = motion {
+                self.motion(motion, count);
+            } else {
+                self.operator = None;
+            }
+        } else if self.mode == Modes::Normal {
+            if char_code == configuration.insert_mode {
+                buffer.begin_change(window);
+                self.mode = Modes::Insert;
+            } else if let Some(shape) = shape_key(configuration, char_code) {
+                self.visual.start = (window.cursor_row, window.cursor_col);
+                self.visual.end = (window.cursor_row, window.cursor_col);
+                self.visual.shape = shape;
+                self.mode = Modes::Visual;
+            } else if char_code == configuration.paste || char_code == configuration.paste_before {
+                match self.registers.read(self.register.unwrap_or('"')) {
+                    Ok(Some(register)) => buffer.paste(window, &register, char_code == configuration.paste_before, count.unwrap_or(1).max(1)),
+                    Ok(None) => {},
+                    Err(err) => self.command = err,
+                }
+            } else if char_code == configuration.undo {
+                if !buffer.undo(window) {
+                    self.command = String::from("Already at oldest change");
+                }
+            } else if char_code == configuration.redo {
+                if !buffer.redo(window) {
+                    self.command = String::from("Already at newest change");
+                }
+            } else if char_code == configuration.window || char_code == 103 || char_code == 34 || Find::from_key(char_code).is_some() {
+                self.count = count;
+                self.pending = Some(char_code);
+            } else if let Some(operator) = Operator::from_key(char_code) {
+                self.start_operator(operator, count);
+            } else if char_code == configuration.yank {
+                self.start_operator(Operator::Yank, count);
+            } else if char_code == 58 {
+                self.command = String::new() + ":";
+                self.mode = Modes::Command;
+            } else if char_code == 47 || char_code == 63 {
+                self.command = String::from(if char_code == 47 { "/" } else { "?" });
+                self.search_start = Some((window.cursor_row, window.cursor_col, window.win_row));
+                self.mode = Modes::Command;
+            } else if char_code == 10 && buffer.is_scratch() && buffer.buf_name == grep::RESULTS {
+                let row = window.cursor_row as usize;
+                if row < self.quickfix.hits.len() {
+                    self.command = self.open_hit(row);
+                }
+            } else if char_code == 111 {
+                buffer.newline_down(window);
+            } else if char_code == 110 || char_code == 78 {
+                self.command = self.search_next(char_code == 78, count.unwrap_or(1).max(1));
+            } else if let Some(motion) = motion {
+                self.motion(motion, count);
+            }
+        } else if self.mode == Modes::Insert {
+            if char_code == configuration.normal_mode {
+                if let Some(block) = self.block_insert.take() {
+                    let (row, col) = (window.cursor_row as usize, window.cursor_col as usize);
+                    // text that broke the line or went back before the block is not repeated
+                    if row == block.row && col > block.col {
+                        let text = buffer.line(row).chars().skip(block.col).take(col - block.col).collect::<String>();
+                        buffer.insert_block(block.rows, block.col, &text, block.pad);
+                    }
+                }
+                buffer.end_change();
+                self.mode = Modes::Normal;
+            } else if char_code == ncurses::KEY_BACKSPACE {
+                buffer.delete(window);
+            } else if char_code == 10 { // if 10 doesnt work try ncurses::KEY_ENTER
+                buffer.newline(window);
+            } else if window.check_move(buffer, char_code) {
+            } else if let Key::Char(character) = key {
+                buffer.insert(window, character);
+                window.right(buffer);
+            }
+        } else if self.mode == Modes::Visual {
+            if char_code == configuration.normal_mode {
+                self.mode = Modes::Normal;
+            } else if let Some(shape) = shape_key(configuration, char_code) {
+                // the key of the shape already selected leaves visual mode, the others switch to theirs
+                if shape == self.visual.shape {
+                    self.mode = Modes::Normal;
+                } else {
+                    self.visual.shape = shape;
+                }
+            } else if char_code == configuration.yank {
+                self.visual_operator(Operator::Yank);
+            } else if char_code == 120 {
+                self.visual_operator(Operator::Delete);
+            } else if let Some(operator) = Operator::from_key(char_code) {
+                self.visual_operator(operator);
+            } else if char_code == 58 {
+                // commands typed from visual mode work on the selected lines
+                self.command = String::from(":'<,'>");
+                self.mode = Modes::Command;
+            } else if (char_code == 73 || char_code == 65) && self.visual.shape == Shape::Block {
+                self.block_insert(char_code == 65);
+            } else if char_code == 103 || char_code == 105 || char_code == 97 || char_code == 34 || Find::from_key(char_code).is_some() {
+                self.count = count;
+                self.pending = Some(char_code);
+            } else if let Some(motion) = motion {
+                self.motion(motion, count);
+            }
+        } else if self.mode == Modes::Command {
+            if char_code == 10 {
+                self.mode = Modes::Normal;
+                let command = std::mem::take(&mut self.command);
+                if self.search_start.is_some() {
+                    self.command = self.finish_search(command);
+                } else {
+                    self.command = self.handle_command(command);
+                }
+            } else if char_code == 27 {
+                self.mode = Modes::Normal;
+                self.command = String::new();
+                self.cancel_search();
+            } else if char_code == ncurses::KEY_BACKSPACE {
+                self.command.pop();
+                // backspacing over the / gives up on the search like <Esc>
+                if self.command.is_empty() && self.search_start.is_some() {
+                    self.mode = Modes::Normal;
+                    self.cancel_search();
+                } else {
+                    self.incremental();
+                }
+            } else if let Key::Char(character) = key {
+                self.command.push(character);
+                self.incremental();
+            }
+        }
+    }
+
+    // moves the cursor of the focused window, in visual mode the selection follows it and
+    // with an operator pending the operator works on the text it moves over instead
+    fn motion(&mut self, motion: Motion, count: Option<usize>) {
+        let window = self.layout.focused();
+        let buffer = &self.buffers.get(window.buffer).buffer;
+        let pos = (window.cursor_row as usize, window.cursor_col as usize);
+        if let Some((operator, operator_count)) = self.operator.take() {
+            if let Some(region) = Region::for_operator(buffer, operator, motion, pos, multiply(operator_count, count)) {
+                self.apply_operator(operator, region);
+            }
+            return;
+        }
+        if let Some((row, col)) = motion.target(buffer, pos, count) {
+            match motion {
+                Motion::Down | Motion::Up => window.vertical(buffer, row as i32, false),
+                _ => window.restore_cursor(buffer, (row as i32, col as i32)),
+            }
+            // after $ the end of every line is where j and k go
+            if motion == Motion::LineEnd {
+                window.desired_col = Some(usize::MAX);
+            }
+        }
+        if self.mode == Modes::Visual {
+            self.visual.end = (window.cursor_row, window.cursor_col);
+        }
+    }
+
+    // hands a text object to the pending operator, or in visual mode grows the selection over it
+    fn text_object(&mut self, object: TextObject, count: Option<usize>) {
+        let window = self.layout.focused();
+        let buffer = &self.buffers.get(window.buffer).buffer;
+        let pos = (window.cursor_row as usize, window.cursor_col as usize);
+        if let Some((operator, operator_count)) = self.operator.take() {
+            if let Some(region) = object.region(buffer, pos, multiply(operator_count, count)) {
+                self.apply_operator(operator, region);
+            }
+            return;
+        }
+
+        let region = match object.region(buffer, pos, count) {
+            Some(region) if self.mode == Modes::Visual && !region.is_empty() => region,
+            _ => return,
+        };
+        if region.linewise && self.visual.shape == Shape::Char {
+            self.visual.shape = Shape::Line;
+        }
+        let (start, last) = if region.linewise {
+            ((region.start.0, 0), (region.end.0, buffer.line_len(region.end.0).saturating_sub(1)))
+        } else if region.end.1 > 0 {
+            (region.start, (region.end.0, region.end.1 - 1))
+        } else {
+            (region.start, (region.end.0 - 1, buffer.line_len(region.end.0 - 1)))
+        };
+        let start = (start.0 as i32, start.1 as i32);
+        let last = (last.0 as i32, last.1 as i32);
+
+        // a selection that already covers more than the cursor keeps what it has
+        if self.visual.start != self.visual.end {
+            self.visual.start = self.visual.start.min(start);
+            self.visual.end = self.visual.end.max(last);
+        } else {
+            self.visual.start = start;
+            self.visual.end = last;
+        }
+        window.restore_cursor(buffer, self.visual.end);
+    }
+
+    // d, c, y, > and < on the selection, which ends visual mode
+    fn visual_operator(&mut self, operator: Operator) {
+        let (start, end) = self.visual.ordered();
+        let (first, last) = (start.0 as usize, end.0 as usize);
+        self.mode = Modes::Normal;
+
+        let window = self.layout.focused();
+        let buffer = &mut self.buffers.get_mut(window.buffer).buffer;
+        match self.visual.shape {
+            Shape::Line => self.apply_operator(operator, Region::lines(first, last)),
+            Shape::Char => {
+                // the char under the end is selected, or the line break when the end is past the last char
+                let end = if end.1 as usize >= buffer.line_len(last) && last + 1 < buffer.len_lines() {
+                    (last + 1, 0)
+                } else {
+                    (last, buffer.next_grapheme(last, end.1 as usize))
+                };
+                self.apply_operator(operator, Region { start: (first, start.1 as usize), end, linewise: false });
+            },
+            Shape::Block => {
+                let (left, right) = self.visual.columns();
+                let cols = left as usize..right as usize + 1;
+                match operator {
+                    Operator::Yank => {
+                        if let Err(err) = self.registers.yank(self.register, Register::new(buffer.block_text(first..=last, cols), Shape::Block)) {
+                            self.command = err;
+                        }
+                        window.restore_cursor(buffer, (first as i32, left));
+                    },
+                    Operator::Delete => {
+                        if let Err(err) = self.registers.delete(self.register, Register::new(buffer.delete_block(window, first..=last, cols), Shape::Block)) {
+                            self.command = err;
+                        }
+                    },
+                    Operator::Change => {
+                        // stays open until <Esc> has repeated the new text on every row
+                        buffer.begin_change(window);
+                        if let Err(err) = self.registers.delete(self.register, Register::new(buffer.delete_block(window, first..=last, cols), Shape::Block)) {
+                            self.command = err;
+                        }
+                        self.block_insert = Some(BlockInsert { row: first, rows: first + 1..=last, col: left as usize, pad: false });
+                        self.mode = Modes::Insert;
+                    },
+                    _ => self.apply_operator(operator, Region::lines(first, last)),
+                }
+            },
+        }
+    }
+
+    // I and A on a block, the text typed on its first row goes on every row at <Esc>
+    fn block_insert(&mut self, append: bool) {
+        let (start, end) = self.visual.ordered();
+        let (left, right) = self.visual.columns();
+        let (first, last) = (start.0 as usize, end.0 as usize);
+        let col = if append { right as usize + 1 } else { left as usize };
+
+        let window = self.layout.focused();
+        let buffer = &mut self.buffers.get_mut(window.buffer).buffer;
+        buffer.begin_change(window);
+        if append {
+            // the first row may end before the block does
+            buffer.insert_block(first..=first, col, "", true);
+        }
+        window.restore_cursor(buffer, (first as i32, col as i32));
+        self.block_insert = Some(BlockInsert { row: first, rows: first + 1..=last, col, pad: append });
+        self.mode = Modes::Insert;
+    }
+
+    // typing an operator twice, as in gUgU, works on lines like gUU does
+    fn start_operator(&mut self, operator: Operator, count: Option<usize>) {
+        match self.operator.take() {
+            Some((pending, operator_count)) if pending == operator => {
+                let window = self.layout.focused();
+                let buffer = &self.buffers.get(window.buffer).buffer;
+                let lines = multiply(operator_count, count).unwrap_or(1).max(1);
+                let first = window.cursor_row as usize;
+                self.apply_operator(operator, Region::lines(first, (first + lines - 1).min(buffer.len_lines() - 1)));
+            },
+            Some(_) => {},
+            None => self.operator = Some((operator, count)),
+        }
+    }
+
+    fn apply_operator(&mut self, operator: Operator, region: Region) {
+        let shape = if region.linewise { Shape::Line } else { Shape::Char };
+        let window = self.layout.focused();
+        let buffer = &mut self.buffers.get_mut(window.buffer).buffer;
+        match operator {
+            Operator::Delete => {
+                if !region.is_empty() {
+                    if let Err(err) = self.registers.delete(self.register, Register::new(buffer.delete_region(window, &region), shape)) {
+                        self.command = err;
+                    }
+                }
+            },
+            Operator::Change => {
+                // the change stays open through insert mode so undo takes back both at once
+                buffer.begin_change(window);
+                if let Err(err) = self.registers.delete(self.register, Register::new(buffer.region_text(&region), shape)) {
+                    self.command = err;
+                }
+                let region = if region.linewise {
+                    let indent = buffer.get_identation(region.start.0);
+                    Region {
+                        start: (region.start.0, indent),
+                        end: (region.end.0, buffer.line_len(region.end.0)),
+                        linewise: false,
+                    }
+                } else {
+                    region
+                };
+                buffer.delete_region(window, &region);
+                self.mode = Modes::Insert;
+            },
+            Operator::Yank => {
+                if let Err(err) = self.registers.yank(self.register, Register::new(buffer.region_text(&region), shape)) {
+                    self.command = err;
+                }
+                let col = if region.linewise { window.cursor_col } else { region.start.1 as i32 };
+                window.restore_cursor(buffer, (region.start.0 as i32, col));
+            },
+            Operator::Indent | Operator::Outdent => buffer.indent_rows(window, &region, operator == Operator::Outdent),
+            Operator::Uppercase | Operator::Lowercase => buffer.map_case(window, &region, operator == Operator::Uppercase),
+        }
+    }
+
+    // moves to the first match of what has been typed after / or ? so far
+    fn incremental(&mut self) {
+        let (row, col, win_row) = match self.search_start {
+            Some(start) => start,
+            None => return,
+        };
+        let window = self.layout.focused();
+        let buffer = &self.buffers.get(window.buffer).buffer;
+        window.win_row = win_row;
+        window.restore_cursor(buffer, (row, col));
+
+        // a pattern that is not finished yet, like one with an open bracket, finds nothing
+        self.preview = compile(&self.command[1..]).ok().filter(|_| self.command.len() > 1);
+        if let Some(pattern) = &self.preview {
+            if let Some(((row, col), _)) = buffer.search(pattern, (row as usize, col as usize), self.command.starts_with('?')) {
+                window.restore_cursor(buffer, (row as i32, col as i32));
+            }
+        }
+    }
+
+    // puts the cursor back where / or ? was typed
+    fn cancel_search(&mut self) {
+        self.preview = None;
+        if let Some((row, col, win_row)) = self.search_start.take() {
+            let window = self.layout.focused();
+            window.win_row = win_row;
+            window.restore_cursor(&self.buffers.get(window.buffer).buffer, (row, col));
+        }
+    }
+
+    // <Enter> after / or ?, with nothing typed the last pattern is searched for again
+    fn finish_search(&mut self, command: String) -> String {
+        self.cancel_search();
+        if command.len() > 1 {
+            match compile(&command[1..]) {
+                Ok(pattern) => self.matches.pattern = Some(pattern),
+                Err(err) => return err,
+            }
+        }
+        self.matches.backward = command.starts_with('?');
+        return self.search_next(false, 1);
+    }
+
+    // n goes on in the direction of the last search and N the other way
+    fn search_next(&mut self, reverse: bool, count: usize) -> String {
+        self.matches.highlight = true;
+        let pattern = match &self.matches.pattern {
+            Some(pattern) => pattern,
+            None => return String::from("No previous regular expression"),
+        };
+        let backward = self.matches.backward != reverse;
+
+        let window = self.layout.focused();
+        let buffer = &self.buffers.get(window.buffer).buffer;
+        let mut pos = (window.cursor_row as usize, window.cursor_col as usize);
+        let mut wrapped = false;
+        for _ in 0..count {
+            match buffer.search(pattern, pos, backward) {
+                Some((found, wrap)) => {
+                    pos = found;
+                    wrapped = wrapped || wrap;
+                },
+                None => return format!("Pattern not found: {}", pattern.as_str()),
+            }
+        }
+        window.restore_cursor(buffer, (pos.0 as i32, pos.1 as i32));
+
+        if wrapped && backward {
+            return String::from("search hit TOP, continuing at BOTTOM");
+        } else if wrapped {
+            return String::from("search hit BOTTOM, continuing at TOP");
+        }
+        return format!("{}{}", if backward { '?' } else { '/' }, pattern.as_str());
+    }
+
+    // editor wide commands, everything else is handed down through the layout
+    fn handle_command(&mut self, command: String) -> String {
+        match command.as_str() {
+            ":q" => {
+                self.quit = true;
+                return String::new();
+            },
+            ":registers" | ":reg" | ":display" | ":di" => return self.registers.list(),
+            ":nohlsearch" | ":noh" => {
+                self.matches.highlight = false;
+                return String::new();
+            },
+            ":cnext" | ":cn" => return self.step_quickfix(true),
+            ":cprevious" | ":cprev" | ":cp" | ":cN" => return self.step_quickfix(false),
+            _ if command == ":grep" || command.starts_with(":grep ") => return self.grep(&command),
+            // the old way to search, the rest of the line is the pattern
+            _ if command.starts_with(":F ") => return self.finish_search(format!("/{}", &command[3..])),
+            _ => {
+                if let Some(message) = self.substitute(&command) {
+                    return message;
+                }
+                return self.layout.handle_command(&mut self.buffers, command, &self.syntaxes);
+            },
+        }
+    }
+
+    // :grep <pattern> [path], what it finds is listed in a buffer of its own in the focused window
+    fn grep(&mut self, command: &str) -> String {
+        // the last word is only taken for the path when there is such a file, so patterns can hold spaces
+        let args = command[":grep".len()..].trim();
+        let (text, path) = match args.rsplit_once(char::is_whitespace) {
+            Some((text, path)) if Path::new(path).exists() => (text.trim_end(), path),
+            _ => (args, "."),
+        };
+        if text.is_empty() {
+            return String::from("Usage: :grep <pattern> [path]");
+        }
+        let pattern = match compile(text) {
+            Ok(pattern) => pattern,
+            Err(err) => return err,
+        };
+        let hits = match grep::search(&pattern, path) {
+            Ok(hits) => hits,
+            Err(err) => return err,
+        };
+        if hits.is_empty() {
+            return format!("No match: {}", text);
+        }
+
+        let text = hits.iter().map(Hit::line).collect::<Vec<String>>().join("\n");
+        let index = self.buffers.scratch(grep::RESULTS, &text);
+        let window = self.layout.focused();
+        self.buffers.switch(window, index);
+        // the results may have replaced the ones this window was already looking at
+        window.win_row = 0;
+        window.restore_cursor(&self.buffers.get(index).buffer, (0, 0));
+
+        let mut files = hits.iter().map(|hit| &hit.path).collect::<Vec<&String>>();
+        files.dedup();
+        let message = format!(
+            "{} match{} in {} file{}",
+            hits.len(),
+            if hits.len() == 1 { "" } else { "es" },
+            files.len(),
+            if files.len() == 1 { "" } else { "s" },
+        );
+        self.quickfix = Quickfix { hits, current: None };
+        return message;
+    }
+
+    // opens the file of the `index`th :grep hit in the focused window with the cursor on the match
+    fn open_hit(&mut self, index: usize) -> String {
+        let hit = self.quickfix.hits[index].clone();
+        let target = match self.buffers.open(&hit.path, &self.syntaxes) {
+            Ok(target) => target,
+            Err(err) => return err,
+        };
+        let window = self.layout.focused();
+        self.buffers.switch(window, target);
+        window.restore_cursor(&self.buffers.get(target).buffer, (hit.row as i32, hit.col as i32));
+        self.quickfix.current = Some(index);
+        return format!("({} of {}) {}", index + 1, self.quickfix.hits.len(), hit.text);
+    }
+
+    // :cnext and :cprev, right after a :grep either one goes to the first hit
+    fn step_quickfix(&mut self, forward: bool) -> String {
+        let len = self.quickfix.hits.len();
+        if len == 0 {
+            return String::from("No :grep results");
+        }
+        let index = match (self.quickfix.current, forward) {
+            (None, _) => 0,
+            (Some(current), true) if current + 1 < len => current + 1,
+            (Some(current), false) if current > 0 => current - 1,
+            _ => return String::from("No more items"),
+        };
+        return self.open_hit(index);
+    }
+
+    // :s with the range in front of it taken off, None when the command is something else
+    fn substitute(&mut self, command: &str) -> Option<String> {
+        let window = self.layout.focused();
+        let buffer = &mut self.buffers.get_mut(window.buffer).buffer;
+        let (start, end) = self.visual.ordered();
+        let lines = Lines {
+            current: window.cursor_row as usize,
+            last: buffer.len_lines() - 1,
+            visual: (start.0 as usize, end.0 as usize),
+        };
+        let (rows, rest) = match substitute::range(command.strip_prefix(':')?, &lines) {
+            Ok(range) => range,
+            Err(err) => return Some(err),
+        };
+        let substitute = match Substitute::parse(rest, self.matches.pattern.as_ref())? {
+            Ok(substitute) => substitute,
+            Err(err) => return Some(err),
+        };
+
+        // n and N go on with the pattern of the :s
+        self.matches.pattern = Some(substitute.pattern.clone());
+        self.matches.highlight = true;
+        // stays open while c asks about each match so undo takes the whole :s back at once
+        buffer.begin_change(window);
+        self.substitution = Some(Substitution::new(substitute, rows));
+        return Some(self.substitute_step(None));
+    }
+
+    // goes on with the :s until the next question, or finishes it and says what was replaced
+    fn substitute_step(&mut self, answer: Option<char>) -> String {
+        let window = self.layout.focused();
+        let buffer = &mut self.buffers.get_mut(window.buffer).buffer;
+        let substitution = match self.substitution.as_mut() {
+            Some(substitution) => substitution,
+            None => return String::new(),
+        };
+        if substitution.run(buffer, window, answer) {
+            return format!("replace with {} (y/n/a/q/l)?", substitution.substitute.replacement);
+        }
+
+        buffer.end_change();
+        let report = substitution.report();
+        self.substitution = None;
+        return report;
+    }
+}
+
+// the count before an operator and the one before its motion multiply, as in 2d3w
+fn multiply(first: Option<usize>, second: Option<usize>) -> Option<usize> {
+    return match (first, second) {
+        (Some(first), Some(second)) => Some(first.saturating_mul(second)),
+        (first, second) => first.or(second),
+    };
+}
+
+// smart case, a pattern without capitals matches either case
+fn compile(pattern: &str) -> Result<Regex, String> {
+    return RegexBuilder::new(pattern)
+        .case_insensitive(!pattern.chars().any(char::is_uppercase))
+        .build()
+        .map_err(|err| err.to_string());
+}
+
+fn shape_key(configuration: &Config, char_code: i32) -> Option<Shape> {
+    if char_code == configuration.visual_mode {
+        return Some(Shape::Char);
+    } else if char_code == configuration.visual_line {
+        return Some(Shape::Line);
+    } else if char_code == configuration.visual_block {
+        return Some(Shape::Block);
+    }
+    return None;
+}
@@ -0,0 +1,64 @@
+use std::fs;
+
+use ignore::WalkBuilder;
+use regex::Regex;
+
+// what the :grep results buffer is called
+pub const RESULTS: &str = "[grep]";
+
+// a line :grep found, row and col are where its first match starts, in chars
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hit {
+    pub path: String,
+    pub row: usize,
+    pub col: usize,
+    pub text: String,
+}
+
+impl Hit {
+    // how the hit reads in the results buffer, counted from 1 like grep -n does
+    pub fn line(&self) -> String {
+        return format!("{}:{}:{}: {}", self.path, self.row + 1, self.col + 1, self.text);
+    }
+}
+
+// every line under `root` that `pattern` matches, skipping what .gitignore and hidden files hide
+// and files that are not text
+pub fn search(pattern: &Regex, root: &str) -> Result<Vec<Hit>, String> {
+    if fs::metadata(root).is_err() {
+        return Err(format!("Cant open {}", root));
+    }
+
+    let mut hits = Vec::new();
+    let walk = WalkBuilder::new(root).require_git(false).sort_by_file_name(|a, b| a.cmp(b)).build();
+    for entry in walk.flatten() {
+        if !entry.file_type().is_some_and(|kind| kind.is_file()) {
+            continue;
+        }
+        let contents = match fs::read_to_string(entry.path()) {
+            Ok(contents) => contents,
+            Err(_) => continue,
+        };
+
+        let path = entry.path().to_string_lossy();
+        let path = path.strip_prefix("./").unwrap_or(&path);
+        for (row, line) in contents.lines().enumerate() {
+            if let Some(found) = pattern.find(line) {
+                hits.push(Hit {
+                    path: path.to_string(),
+                    row,
+                    col: line[..found.start()].chars().count(),
+                    text: line.trim().to_string(),
+                });
+            }
+        }
+    }
+    return Ok(hits);
+}
+
+// the :grep results and the one :cnext and :cprev last went to
+#[derive(Debug, Default)]
+pub struct Quickfix {
+    pub hits: Vec<Hit>,
+    pub current: Option<usize>,
+}
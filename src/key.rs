@@ -0,0 +1,98 @@
+use crate::backend::Backend;
+
+// a key press as the editor sees it, multibyte input already assembled into one character
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    // keys without a character, numbered like getch does, e.g. ncurses::KEY_LEFT
+    Code(i32),
+    // everything the terminal sent between the bracketed paste markers
+    Paste(String),
+}
+
+impl Key {
+    // the number config bindings are compared with, characters outside ascii never match one
+    pub fn code(&self) -> i32 {
+        return match self {
+            Key::Char(character) if character.is_ascii() => *character as i32,
+            Key::Char(_) | Key::Paste(_) => ncurses::ERR,
+            Key::Code(code) => *code,
+        };
+    }
+
+    // getch hands multibyte characters over one byte at a time, so the continuation bytes are read here
+    pub fn read(backend: &mut dyn Backend) -> Option<Key> {
+        let code = backend.read_key();
+        if code == ncurses::ERR {
+            return None;
+        }
+        if !(0..256).contains(&code) {
+            return Some(Key::Code(code));
+        }
+        if code == 27 {
+            if let Some(text) = read_paste(backend) {
+                return Some(Key::Paste(text));
+            }
+        }
+
+        let lead = code as u8;
+        let len = match lead {
+            0x00..=0x7f => 1,
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => return Some(Key::Code(code)),
+        };
+
+        let mut bytes = vec![lead];
+        for _ in 1..len {
+            bytes.push(backend.read_key() as u8);
+        }
+        return match String::from_utf8(bytes).ok().and_then(|text| text.chars().next()) {
+            Some(character) => Some(Key::Char(character)),
+            None => Some(Key::Code(code)),
+        };
+    }
+}
+
+// a paste arrives as ESC [200~ text ESC [201~, whatever else followed the ESC is put back
+fn read_paste(backend: &mut dyn Backend) -> Option<String> {
+    let mut read = Vec::new();
+    for expected in "[200~".bytes() {
+        let key = backend.read_pending();
+        if key != ncurses::ERR {
+            read.push(key);
+        }
+        if key != expected as i32 {
+            for key in read.into_iter().rev() {
+                backend.unread_key(key);
+            }
+            return None;
+        }
+    }
+
+    let mut bytes = Vec::new();
+    loop {
+        let key = backend.read_key();
+        if key == ncurses::ERR {
+            break;
+        }
+        // keypad translations have no place in pasted text
+        if !(0..256).contains(&key) {
+            continue;
+        }
+        bytes.push(key as u8);
+        if bytes.ends_with(b"\x1b[201~") {
+            bytes.truncate(bytes.len() - 6);
+            break;
+        }
+    }
+    // terminals send the line breaks of a paste as carriage returns
+    return Some(String::from_utf8_lossy(&bytes).replace("\r\n", "\n").replace('\r', "\n"));
+}
+
+impl From<char> for Key {
+    fn from(character: char) -> Key {
+        return Key::Char(character);
+    }
+}
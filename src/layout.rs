@@ -0,0 +1,544 @@
+use regex::Regex;
+
+use crate::backend::Backend;
+use crate::buffer::list::BufferList;
+use crate::syntax::Registry;
+use crate::window::clipped;
+use crate::{Modes, Visual, Window};
+
+// Stacked puts windows above one another like :split, SideBySide next to each other like :vsplit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Stacked,
+    SideBySide,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+#[derive(Debug)]
+enum Node {
+    Window(usize),
+    Split {
+        direction: Direction,
+        ratio: f32,
+        first: Box<Node>,
+        second: Box<Node>,
+    },
+}
+
+// windows live in a flat list, the tree only decides where on screen each of them goes
+#[derive(Debug)]
+pub struct Layout {
+    pub windows: Vec<Window>,
+    pub focused: usize,
+    root: Node,
+    height: i32,
+    width: i32,
+    separators: Vec<(i32, i32, i32)>,
+}
+
+// smallest window along a direction, a stacked window needs a text row and its status bar
+fn min_size(direction: Direction) -> i32 {
+    return match direction {
+        Direction::Stacked => 2,
+        Direction::SideBySide => 1,
+    };
+}
+
+// space a split shares out between its children, side by side windows lose a column to the separator
+fn extent(direction: Direction, height: i32, width: i32) -> i32 {
+    return match direction {
+        Direction::Stacked => height,
+        Direction::SideBySide => width - 1,
+    };
+}
+
+fn first_size(direction: Direction, ratio: f32, extent: i32) -> i32 {
+    let min = min_size(direction);
+    return ((ratio * extent as f32).round() as i32).min(extent - min).max(min);
+}
+
+impl Node {
+    fn contains(&self, target: usize) -> bool {
+        return match self {
+            Node::Window(index) => *index == target,
+            Node::Split { first, second, .. } => first.contains(target) || second.contains(target),
+        };
+    }
+
+    fn leaves(&self, out: &mut Vec<usize>) {
+        match self {
+            Node::Window(index) => out.push(*index),
+            Node::Split { first, second, .. } => {
+                first.leaves(out);
+                second.leaves(out);
+            },
+        }
+    }
+
+    fn arrange(&self, windows: &mut [Window], separators: &mut Vec<(i32, i32, i32)>, top: i32, left: i32, height: i32, width: i32) {
+        match self {
+            Node::Window(index) => {
+                let window = &mut windows[*index];
+                window.win_top = top;
+                window.win_left = left;
+                window.win_height = height;
+                window.win_width = width;
+                window.scroll_to_cursor();
+            },
+            Node::Split { direction, ratio, first, second } => {
+                let size = first_size(*direction, *ratio, extent(*direction, height, width));
+                match direction {
+                    Direction::Stacked => {
+                        first.arrange(windows, separators, top, left, size, width);
+                        second.arrange(windows, separators, top + size, left, height - size, width);
+                    },
+                    Direction::SideBySide => {
+                        first.arrange(windows, separators, top, left, height, size);
+                        separators.push((top, left + size, height));
+                        second.arrange(windows, separators, top, left + size + 1, height, width - size - 1);
+                    },
+                }
+            },
+        }
+    }
+
+    // the new window takes the first half, so it ends up above or left of the one it was split from
+    fn split(&mut self, target: usize, new: usize, direction: Direction) -> bool {
+        match self {
+            Node::Window(index) if *index == target => {
+                *self = Node::Split {
+                    direction,
+                    ratio: 0.5,
+                    first: Box::new(Node::Window(new)),
+                    second: Box::new(Node::Window(target)),
+                };
+                return true;
+            },
+            Node::Window(_) => return false,
+            Node::Split { first, second, .. } => return first.split(target, new, direction) || second.split(target, new, direction),
+        }
+    }
+
+    // the sibling of a removed window takes over its parent's place
+    fn remove(&mut self, target: usize) -> bool {
+        let sibling = match self {
+            Node::Window(_) => return false,
+            Node::Split { first, second, .. } => {
+                if matches!(**first, Node::Window(index) if index == target) {
+                    std::mem::replace(&mut **second, Node::Window(0))
+                } else if matches!(**second, Node::Window(index) if index == target) {
+                    std::mem::replace(&mut **first, Node::Window(0))
+                } else {
+                    return first.remove(target) || second.remove(target);
+                }
+            },
+        };
+        *self = sibling;
+        return true;
+    }
+
+    fn renumber(&mut self, removed: usize) {
+        match self {
+            Node::Window(index) => {
+                if *index > removed {
+                    *index -= 1;
+                }
+            },
+            Node::Split { first, second, .. } => {
+                first.renumber(removed);
+                second.renumber(removed);
+            },
+        }
+    }
+
+    // moves the edge of the innermost split in `direction` that holds the target window
+    fn resize(&mut self, target: usize, direction: Direction, delta: i32, height: i32, width: i32) -> bool {
+        let (split, ratio, first, second) = match self {
+            Node::Window(_) => return false,
+            Node::Split { direction, ratio, first, second } => (*direction, ratio, first, second),
+        };
+
+        let extent = extent(split, height, width);
+        let size = first_size(split, *ratio, extent);
+        let (first_height, first_width, second_height, second_width) = match split {
+            Direction::Stacked => (size, width, height - size, width),
+            Direction::SideBySide => (height, size, height, width - size - 1),
+        };
+
+        let in_first = first.contains(target);
+        if in_first && first.resize(target, direction, delta, first_height, first_width) {
+            return true;
+        }
+        if !in_first && second.resize(target, direction, delta, second_height, second_width) {
+            return true;
+        }
+        if split != direction || extent < 2 * min_size(split) {
+            return false;
+        }
+
+        let size = if in_first { size + delta } else { size - delta };
+        *ratio = size.min(extent - min_size(split)).max(min_size(split)) as f32 / extent as f32;
+        return true;
+    }
+
+    fn equalize(&mut self) {
+        if let Node::Split { ratio, first, second, .. } = self {
+            *ratio = 0.5;
+            first.equalize();
+            second.equalize();
+        }
+    }
+}
+
+impl Layout {
+    pub fn new(window: Window, height: i32, width: i32) -> Layout {
+        let mut layout = Layout {
+            windows: vec![window],
+            focused: 0,
+            root: Node::Window(0),
+            height,
+            width,
+            separators: Vec::new(),
+        };
+        layout.arrange();
+        return layout;
+    }
+
+    pub fn focused(&mut self) -> &mut Window {
+        return &mut self.windows[self.focused];
+    }
+
+    pub fn resize_screen(&mut self, height: i32, width: i32) {
+        self.height = height.max(1);
+        self.width = width.max(1);
+        self.arrange();
+    }
+
+    // windows get all rows but the last one, which holds the command line
+    fn arrange(&mut self) {
+        self.separators.clear();
+        self.root.arrange(&mut self.windows, &mut self.separators, 0, 0, self.height, self.width);
+    }
+
+    pub fn split(&mut self, direction: Direction) -> Result<(), String> {
+        let window = &self.windows[self.focused];
+        let (size, min) = match direction {
+            Direction::Stacked => (window.win_height, 2 * min_size(direction)),
+            Direction::SideBySide => (window.win_width, 2 * min_size(direction) + 1),
+        };
+        if size < min {
+            return Err(String::from("Not enough room"));
+        }
+
+        let new = Window {
+            win_top: window.win_top,
+            win_left: window.win_left,
+            ..*window
+        };
+        self.windows.push(new);
+        self.root.split(self.focused, self.windows.len() - 1, direction);
+        self.focused = self.windows.len() - 1;
+        self.arrange();
+        return Ok(());
+    }
+
+    pub fn close(&mut self) -> Result<(), String> {
+        if self.windows.len() == 1 {
+            return Err(String::from("Cannot close last window"));
+        }
+
+        let mut order = Vec::new();
+        self.root.leaves(&mut order);
+        let position = order.iter().position(|index| *index == self.focused).unwrap();
+        let next = if position == 0 { order[1] } else { order[position - 1] };
+
+        let removed = self.focused;
+        self.root.remove(removed);
+        self.root.renumber(removed);
+        self.windows.remove(removed);
+        self.focused = if next > removed { next - 1 } else { next };
+        self.arrange();
+        return Ok(());
+    }
+
+    pub fn only(&mut self) {
+        let window = self.windows.swap_remove(self.focused);
+        self.windows = vec![window];
+        self.focused = 0;
+        self.root = Node::Window(0);
+        self.arrange();
+    }
+
+    pub fn resize(&mut self, direction: Direction, delta: i32) {
+        self.root.resize(self.focused, direction, delta, self.height, self.width);
+        self.arrange();
+    }
+
+    pub fn equalize(&mut self) {
+        self.root.equalize();
+        self.arrange();
+    }
+
+    pub fn cycle(&mut self) {
+        let mut order = Vec::new();
+        self.root.leaves(&mut order);
+        let position = order.iter().position(|index| *index == self.focused).unwrap();
+        self.focused = order[(position + 1) % order.len()];
+    }
+
+    // picks the neighbour on `side` that lies beside the cursor, or failing that the first one touching
+    pub fn focus(&mut self, side: Side) {
+        let window = &self.windows[self.focused];
+        let row = window.win_top + window.cursor_row - window.win_row;
+        let col = window.win_left;
+
+        let mut best = None;
+        for (index, other) in self.windows.iter().enumerate() {
+            let (touches, overlaps, beside) = match side {
+                Side::Left => (other.win_left + other.win_width + 1 == window.win_left, other.win_top < window.win_top + window.win_height && window.win_top < other.win_top + other.win_height, other.win_top <= row && row < other.win_top + other.win_height),
+                Side::Right => (window.win_left + window.win_width + 1 == other.win_left, other.win_top < window.win_top + window.win_height && window.win_top < other.win_top + other.win_height, other.win_top <= row && row < other.win_top + other.win_height),
+                Side::Up => (other.win_top + other.win_height == window.win_top, other.win_left < window.win_left + window.win_width && window.win_left < other.win_left + other.win_width, other.win_left <= col && col < other.win_left + other.win_width),
+                Side::Down => (window.win_top + window.win_height == other.win_top, other.win_left < window.win_left + window.win_width && window.win_left < other.win_left + other.win_width, other.win_left <= col && col < other.win_left + other.win_width),
+            };
+            if touches && overlaps && (beside || best.is_none()) {
+                best = Some(index);
+                if beside {
+                    break;
+                }
+            }
+        }
+
+        if let Some(index) = best {
+            self.focused = index;
+        }
+    }
+
+    // the matches of `search` are highlighted in every window, the selection only in the focused one
+    pub fn display(
+        &mut self,
+        backend: &mut dyn Backend,
+        buffers: &mut BufferList,
+        mode: &Modes,
+        selection: Option<&Visual>,
+        search: Option<&Regex>,
+        command: &str,
+    ) {
+        for (index, window) in self.windows.iter_mut().enumerate() {
+            let entry = buffers.get_mut(window.buffer);
+            entry.syntax.update(&mut entry.buffer);
+            // another window may have deleted the lines this one was looking at
+            window.fit(&entry.buffer);
+            let (mode, selection) = if index == self.focused { (Some(mode), selection) } else { (None, None) };
+            window.display(&entry.buffer, backend, mode, selection, search, &mut entry.syntax);
+        }
+
+        for (top, col, height) in &self.separators {
+            for row in *top..top + height {
+                backend.draw(row, *col, "│", 3);
+            }
+        }
+
+        // a message of several lines grows up over the windows, the bottom right cell is left
+        // alone since writing there scrolls the screen
+        let lines = command.split('\n').collect::<Vec<&str>>();
+        let shown = &lines[lines.len().saturating_sub(self.height as usize + 1)..];
+        for (index, line) in shown.iter().enumerate() {
+            let (line, width) = clipped(line, 0, self.width as usize - 1);
+            let row = self.height + 1 - shown.len() as i32 + index as i32;
+            backend.draw(row, 0, &format!("{}{}", line, " ".repeat(self.width as usize - 1 - width)), 1);
+        }
+
+        let window = &self.windows[self.focused];
+        let buffer = &buffers.get(window.buffer).buffer;
+        let cursor_x = buffer.display_col(window.cursor_row as usize, window.cursor_col as usize) as i32;
+        backend.move_cursor(window.win_top + window.cursor_row - window.win_row, window.win_left + cursor_x.min(window.win_width - 1));
+    }
+
+    fn open(&mut self, buffers: &mut BufferList, direction: Direction, args: &[&str], syntaxes: &Registry) -> String {
+        let index = if args.is_empty() {
+            None
+        } else {
+            match buffers.open(&args.join(" "), syntaxes) {
+                Ok(index) => Some(index),
+                Err(err) => return err,
+            }
+        };
+
+        if let Err(err) = self.split(direction) {
+            return err;
+        }
+        if let Some(index) = index {
+            buffers.switch(&mut self.windows[self.focused], index);
+        }
+        return String::new();
+    }
+
+    // window commands are handled here, the rest is passed on to the buffer list
+    pub fn handle_command(
+        &mut self,
+        buffers: &mut BufferList,
+        command: String,
+        syntaxes: &Registry,
+    ) -> String {
+        let args = command.split(' ').collect::<Vec<&str>>();
+        let (direction, resize) = match args[0] {
+            ":vertical" | ":vert" => (Direction::SideBySide, &args[1..]),
+            _ => (Direction::Stacked, &args[..]),
+        };
+
+        match args[0] {
+            ":split" | ":sp" => return self.open(buffers, Direction::Stacked, &args[1..], syntaxes),
+            ":vsplit" | ":vs" => return self.open(buffers, Direction::SideBySide, &args[1..], syntaxes),
+            ":close" | ":clo" => return self.close().err().unwrap_or_default(),
+            ":only" | ":on" => {
+                self.only();
+                return String::new();
+            },
+            _ => {},
+        }
+
+        if matches!(resize.first(), Some(&":resize" | &":res" | &"resize" | &"res")) {
+            let amount = resize.get(1).copied().unwrap_or_default();
+            let window = &self.windows[self.focused];
+            let current = match direction {
+                Direction::Stacked => window.win_height - 1,
+                Direction::SideBySide => window.win_width,
+            };
+            let delta = match (amount.strip_prefix('+'), amount.strip_prefix('-')) {
+                (Some(more), _) => more.parse::<i32>().ok(),
+                (_, Some(less)) => less.parse::<i32>().ok().map(|less| -less),
+                _ => amount.parse::<i32>().ok().map(|size| size - current),
+            };
+            match delta {
+                Some(delta) => {
+                    self.resize(direction, delta);
+                    return String::new();
+                },
+                None => return format!("Invalid size: {}", amount),
+            }
+        }
+
+        return buffers.handle_command(&mut self.windows, self.focused, command, syntaxes);
+    }
+
+    // the key after the window prefix, as in <C-w>s
+    pub fn handle_key(&mut self, key: i32) -> String {
+        let result = match key {
+            115 | 83 => self.split(Direction::Stacked),
+            118 => self.split(Direction::SideBySide),
+            99 | 113 => self.close(),
+            _ => Ok(()),
+        };
+        match key {
+            104 | ncurses::KEY_LEFT => self.focus(Side::Left),
+            106 | ncurses::KEY_DOWN => self.focus(Side::Down),
+            107 | ncurses::KEY_UP => self.focus(Side::Up),
+            108 | ncurses::KEY_RIGHT => self.focus(Side::Right),
+            119 | 23 => self.cycle(),
+            111 => self.only(),
+            43 => self.resize(Direction::Stacked, 1),
+            45 => self.resize(Direction::Stacked, -1),
+            62 => self.resize(Direction::SideBySide, 1),
+            60 => self.resize(Direction::SideBySide, -1),
+            61 => self.equalize(),
+            _ => {},
+        }
+        return result.err().unwrap_or_default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(height: i32, width: i32) -> Layout {
+        let window = Window { cursor_col: 0, cursor_row: 0, win_row: 0, win_width: 0, win_height: 0, win_top: 0, win_left: 0, buffer: 0, desired_col: None };
+        return Layout::new(window, height, width);
+    }
+
+    // (top, left, height, width) of every window
+    fn placed(layout: &Layout) -> Vec<(i32, i32, i32, i32)> {
+        return layout.windows.iter().map(|window| (window.win_top, window.win_left, window.win_height, window.win_width)).collect();
+    }
+
+    #[test]
+    fn split_and_close_windows() {
+        let mut layout = layout(9, 21);
+        layout.split(Direction::Stacked).unwrap();
+        // the new window goes above and takes the focus
+        assert_eq!(placed(&layout), vec![(5, 0, 4, 21), (0, 0, 5, 21)]);
+        assert_eq!(layout.focused, 1);
+
+        layout.split(Direction::SideBySide).unwrap();
+        assert_eq!(placed(&layout), vec![(5, 0, 4, 21), (0, 11, 5, 10), (0, 0, 5, 10)]);
+        assert_eq!(layout.separators, vec![(0, 10, 5)]);
+
+        // the focus goes to the window before the closed one
+        layout.close().unwrap();
+        assert_eq!(placed(&layout), vec![(5, 0, 4, 21), (0, 0, 5, 21)]);
+        assert_eq!(layout.focused, 1);
+        layout.close().unwrap();
+        assert_eq!(placed(&layout), vec![(0, 0, 9, 21)]);
+        assert_eq!(layout.close(), Err(String::from("Cannot close last window")));
+    }
+
+    #[test]
+    fn splits_need_room() {
+        let mut layout = layout(3, 2);
+        assert_eq!(layout.split(Direction::Stacked), Err(String::from("Not enough room")));
+        assert_eq!(layout.split(Direction::SideBySide), Err(String::from("Not enough room")));
+        assert_eq!(layout.windows.len(), 1);
+    }
+
+    #[test]
+    fn splits_are_resized() {
+        let mut layout = layout(9, 21);
+        layout.split(Direction::SideBySide).unwrap();
+        layout.resize(Direction::SideBySide, -5);
+        assert_eq!(placed(&layout), vec![(0, 6, 9, 15), (0, 0, 9, 5)]);
+        layout.handle_key(62);
+        layout.handle_key(62);
+        assert_eq!(layout.focused().win_width, 7);
+        layout.handle_key(61);
+        assert_eq!(layout.focused().win_width, 10);
+
+        // a stacked window keeps a text row above its status bar
+        layout.split(Direction::Stacked).unwrap();
+        layout.resize(Direction::Stacked, -10);
+        assert_eq!(layout.focused().win_height, 2);
+        // no stacked split holds the other window, so there is nothing to resize
+        layout.focused = 0;
+        layout.resize(Direction::Stacked, 3);
+        assert_eq!(layout.windows[0].win_height, 9);
+    }
+
+    #[test]
+    fn focus_moves_between_windows() {
+        let mut layout = layout(9, 21);
+        layout.split(Direction::Stacked).unwrap();
+        layout.split(Direction::SideBySide).unwrap();
+        assert_eq!(layout.focused, 2);
+
+        layout.handle_key(108);
+        assert_eq!(layout.focused, 1);
+        layout.handle_key(106);
+        assert_eq!(layout.focused, 0);
+        layout.handle_key(107);
+        assert_eq!(layout.focused, 2);
+        // there is nothing further up, so the focus stays
+        layout.handle_key(107);
+        assert_eq!(layout.focused, 2);
+
+        layout.handle_key(119);
+        assert_eq!(layout.focused, 1);
+        layout.handle_key(111);
+        assert_eq!(placed(&layout), vec![(0, 0, 9, 21)]);
+    }
+}
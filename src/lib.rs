@@ -0,0 +1,22 @@
+#![allow(clippy::needless_return)]
+
+extern crate ncurses;
+pub mod backend;
+pub mod buffer;
+pub mod clipboard;
+pub mod config;
+pub mod editor;
+pub mod grep;
+pub mod key;
+pub mod layout;
+pub mod motion;
+pub mod operator;
+pub mod register;
+pub mod substitute;
+pub mod syntax;
+pub mod textobject;
+pub mod window;
+
+pub use editor::{Editor, Matched, Modes, Shape, Visual};
+pub use key::Key;
+pub use window::Window;
@@ -0,0 +1,397 @@
+use crate::buffer::Buffer;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Find {
+    // f, t, F and T
+    To,
+    Till,
+    BackTo,
+    BackTill,
+}
+
+// how much of the text between the cursor and the target an operator takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Exclusive,
+    Inclusive,
+    Linewise,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Motion {
+    Left,
+    Right,
+    Down,
+    Up,
+    // the flag picks WORDs, runs of anything but whitespace, over words
+    WordForward(bool),
+    WordBackward(bool),
+    WordEnd(bool),
+    LineStart,
+    FirstNonBlank,
+    LineEnd,
+    // gg and G, a count names the line to go to
+    FirstLine,
+    LastLine,
+    ParagraphForward,
+    ParagraphBackward,
+    MatchingBracket,
+    // repeated is set for ; and , so a till doesn't stick to the character next to it
+    Find { kind: Find, character: char, repeated: bool },
+}
+
+impl Find {
+    pub fn from_key(char_code: i32) -> Option<Find> {
+        return match char_code {
+            102 => Some(Find::To),
+            116 => Some(Find::Till),
+            70 => Some(Find::BackTo),
+            84 => Some(Find::BackTill),
+            _ => None,
+        };
+    }
+
+    pub fn reversed(self) -> Find {
+        return match self {
+            Find::To => Find::BackTo,
+            Find::Till => Find::BackTill,
+            Find::BackTo => Find::To,
+            Find::BackTill => Find::Till,
+        };
+    }
+}
+
+impl Motion {
+    // motions that are a single key, the ones taking a second key are put together by the editor
+    pub fn from_key(char_code: i32) -> Option<Motion> {
+        return match char_code {
+            104 => Some(Motion::Left),
+            106 => Some(Motion::Down),
+            107 => Some(Motion::Up),
+            108 => Some(Motion::Right),
+            119 => Some(Motion::WordForward(false)),
+            98 => Some(Motion::WordBackward(false)),
+            101 => Some(Motion::WordEnd(false)),
+            87 => Some(Motion::WordForward(true)),
+            66 => Some(Motion::WordBackward(true)),
+            69 => Some(Motion::WordEnd(true)),
+            48 => Some(Motion::LineStart),
+            94 => Some(Motion::FirstNonBlank),
+            36 => Some(Motion::LineEnd),
+            71 => Some(Motion::LastLine),
+            125 => Some(Motion::ParagraphForward),
+            123 => Some(Motion::ParagraphBackward),
+            37 => Some(Motion::MatchingBracket),
+            _ if char_code == ncurses::KEY_LEFT => Some(Motion::Left),
+            _ if char_code == ncurses::KEY_RIGHT => Some(Motion::Right),
+            _ if char_code == ncurses::KEY_DOWN => Some(Motion::Down),
+            _ if char_code == ncurses::KEY_UP => Some(Motion::Up),
+            _ => None,
+        };
+    }
+
+    pub fn kind(self) -> Kind {
+        return match self {
+            Motion::Down | Motion::Up | Motion::FirstLine | Motion::LastLine => Kind::Linewise,
+            Motion::WordEnd(_) | Motion::LineEnd | Motion::MatchingBracket => Kind::Inclusive,
+            Motion::Find { kind: Find::To | Find::Till, .. } => Kind::Inclusive,
+            _ => Kind::Exclusive,
+        };
+    }
+
+    // where the motion lands from `pos`, None when it can't go anywhere, like f without a match
+    pub fn target(self, buffer: &Buffer, pos: (usize, usize), count: Option<usize>) -> Option<(usize, usize)> {
+        let times = count.unwrap_or(1).max(1);
+        let last_row = buffer.len_lines() - 1;
+        let (row, col) = pos;
+
+        match self {
+            Motion::Left => {
+                let mut col = col;
+                for _ in 0..times {
+                    col = buffer.prev_grapheme(row, col);
+                }
+                return Some((row, col));
+            },
+            Motion::Right => {
+                let mut col = col;
+                for _ in 0..times {
+                    let next = buffer.next_grapheme(row, col);
+                    if next >= buffer.line_len(row) {
+                        break;
+                    }
+                    col = next;
+                }
+                return Some((row, col));
+            },
+            Motion::Down => return Some(((row + times).min(last_row), col)),
+            Motion::Up => return Some((row.saturating_sub(times), col)),
+            Motion::WordForward(big) => return Some(repeat(times, pos, |pos| word_forward(buffer, pos, big))),
+            Motion::WordBackward(big) => return Some(repeat(times, pos, |pos| word_backward(buffer, pos, big))),
+            Motion::WordEnd(big) => return Some(repeat(times, pos, |pos| word_end(buffer, pos, big))),
+            Motion::LineStart => return Some((row, 0)),
+            Motion::FirstNonBlank => return Some((row, first_non_blank(buffer, row))),
+            Motion::LineEnd => {
+                let row = (row + times - 1).min(last_row);
+                return Some((row, buffer.line_len(row).saturating_sub(1)));
+            },
+            Motion::FirstLine | Motion::LastLine => {
+                let row = match (count, self) {
+                    (Some(count), _) => count.max(1).min(last_row + 1) - 1,
+                    (None, Motion::FirstLine) => 0,
+                    (None, _) => last_row,
+                };
+                return Some((row, first_non_blank(buffer, row)));
+            },
+            Motion::ParagraphForward => return Some(repeat(times, pos, |pos| paragraph_forward(buffer, pos))),
+            Motion::ParagraphBackward => return Some(repeat(times, pos, |pos| paragraph_backward(buffer, pos))),
+            Motion::MatchingBracket => return matching_bracket(buffer, pos),
+            Motion::Find { kind, character, repeated } => {
+                let mut col = col;
+                for index in 0..times {
+                    col = find(buffer, (row, col), kind, character, repeated || index > 0)?;
+                }
+                return Some((row, col));
+            },
+        }
+    }
+}
+
+fn repeat(times: usize, pos: (usize, usize), step: impl Fn((usize, usize)) -> (usize, usize)) -> (usize, usize) {
+    let mut pos = pos;
+    for _ in 0..times {
+        pos = step(pos);
+    }
+    return pos;
+}
+
+// whitespace is 0, words are made of letters, digits and underscores and punctuation runs are words too
+pub fn class(character: Option<char>, big: bool) -> u8 {
+    return match character {
+        None => 0,
+        Some(character) if character.is_whitespace() => 0,
+        Some(_) if big => 1,
+        Some(character) if character.is_alphanumeric() || character == '_' => 2,
+        Some(_) => 1,
+    };
+}
+
+pub fn first_non_blank(buffer: &Buffer, row: usize) -> usize {
+    let line = buffer.line(row);
+    return match line.chars().position(|character| !character.is_whitespace()) {
+        Some(col) => col,
+        None => line.chars().count().saturating_sub(1),
+    };
+}
+
+// steps through the buffer a char at a time, an empty line is a single stop without a char
+pub struct Walker<'a> {
+    buffer: &'a Buffer,
+    pub row: usize,
+    pub col: usize,
+    line: Vec<char>,
+}
+
+impl<'a> Walker<'a> {
+    pub fn new(buffer: &'a Buffer, (row, col): (usize, usize)) -> Walker<'a> {
+        return Walker {
+            buffer,
+            row,
+            col,
+            line: buffer.line(row).chars().collect(),
+        };
+    }
+
+    pub fn char(&self) -> Option<char> {
+        return self.line.get(self.col).copied();
+    }
+
+    pub fn pos(&self) -> (usize, usize) {
+        return (self.row, self.col);
+    }
+
+    pub fn forward(&mut self) -> bool {
+        if self.col + 1 < self.line.len() {
+            self.col += 1;
+        } else if self.row + 1 < self.buffer.len_lines() {
+            self.row += 1;
+            self.col = 0;
+            self.line = self.buffer.line(self.row).chars().collect();
+        } else {
+            return false;
+        }
+        return true;
+    }
+
+    pub fn backward(&mut self) -> bool {
+        if self.col > 0 {
+            self.col -= 1;
+        } else if self.row > 0 {
+            self.row -= 1;
+            self.line = self.buffer.line(self.row).chars().collect();
+            self.col = self.line.len().saturating_sub(1);
+        } else {
+            return false;
+        }
+        return true;
+    }
+}
+
+pub fn word_forward(buffer: &Buffer, pos: (usize, usize), big: bool) -> (usize, usize) {
+    let mut walker = Walker::new(buffer, pos);
+    let start = class(walker.char(), big);
+    let mut row = walker.row;
+
+    // leave the current word, a line break ends it too
+    while start != 0 && class(walker.char(), big) == start {
+        if !walker.forward() {
+            return walker.pos();
+        }
+        if walker.row != row {
+            break;
+        }
+    }
+
+    // then the blanks after it, an empty line counts as a word of its own
+    loop {
+        if walker.row != row && walker.char().is_none() {
+            return walker.pos();
+        }
+        if class(walker.char(), big) != 0 {
+            return walker.pos();
+        }
+        row = walker.row;
+        if !walker.forward() {
+            return walker.pos();
+        }
+    }
+}
+
+fn word_backward(buffer: &Buffer, pos: (usize, usize), big: bool) -> (usize, usize) {
+    let mut walker = Walker::new(buffer, pos);
+    if !walker.backward() {
+        return pos;
+    }
+
+    while class(walker.char(), big) == 0 {
+        if walker.char().is_none() {
+            return walker.pos();
+        }
+        if !walker.backward() {
+            return walker.pos();
+        }
+    }
+
+    let start = class(walker.char(), big);
+    loop {
+        let row = walker.row;
+        if !walker.backward() {
+            return walker.pos();
+        }
+        if walker.row != row || class(walker.char(), big) != start {
+            walker.forward();
+            return walker.pos();
+        }
+    }
+}
+
+pub fn word_end(buffer: &Buffer, pos: (usize, usize), big: bool) -> (usize, usize) {
+    let mut walker = Walker::new(buffer, pos);
+    if !walker.forward() {
+        return pos;
+    }
+
+    while class(walker.char(), big) == 0 {
+        if !walker.forward() {
+            return walker.pos();
+        }
+    }
+
+    let start = class(walker.char(), big);
+    loop {
+        let row = walker.row;
+        if !walker.forward() {
+            return walker.pos();
+        }
+        if walker.row != row || class(walker.char(), big) != start {
+            walker.backward();
+            return walker.pos();
+        }
+    }
+}
+
+// paragraphs are separated by empty lines, running off either end lands on the first or last char
+fn paragraph_forward(buffer: &Buffer, (row, _): (usize, usize)) -> (usize, usize) {
+    let lines = buffer.len_lines();
+    let mut row = row + 1;
+    while row < lines && buffer.line_len(row) == 0 {
+        row += 1;
+    }
+    while row < lines && buffer.line_len(row) != 0 {
+        row += 1;
+    }
+    if row >= lines {
+        return (lines - 1, buffer.line_len(lines - 1).saturating_sub(1));
+    }
+    return (row, 0);
+}
+
+fn paragraph_backward(buffer: &Buffer, (row, _): (usize, usize)) -> (usize, usize) {
+    let mut row = row.saturating_sub(1);
+    while row > 0 && buffer.line_len(row) == 0 {
+        row -= 1;
+    }
+    while row > 0 && buffer.line_len(row) != 0 {
+        row -= 1;
+    }
+    return (row, 0);
+}
+
+// like vim, the bracket may also be further along the line than the cursor
+fn matching_bracket(buffer: &Buffer, (row, col): (usize, usize)) -> Option<(usize, usize)> {
+    let line = buffer.line(row).chars().collect::<Vec<char>>();
+    let (start, bracket) = line.iter().enumerate().skip(col).find(|(_, character)| "()[]{}".contains(**character))?;
+
+    let (open, close, forward) = match bracket {
+        '(' => ('(', ')', true),
+        '[' => ('[', ']', true),
+        '{' => ('{', '}', true),
+        ')' => ('(', ')', false),
+        ']' => ('[', ']', false),
+        _ => ('{', '}', false),
+    };
+
+    let mut walker = Walker::new(buffer, (row, start));
+    let mut depth = 0;
+    loop {
+        match walker.char() {
+            Some(character) if character == open => depth += if forward { 1 } else { -1 },
+            Some(character) if character == close => depth += if forward { -1 } else { 1 },
+            _ => {},
+        }
+        if depth == 0 {
+            return Some(walker.pos());
+        }
+        let moved = if forward { walker.forward() } else { walker.backward() };
+        if !moved {
+            return None;
+        }
+    }
+}
+
+fn find(buffer: &Buffer, (row, col): (usize, usize), kind: Find, character: char, repeated: bool) -> Option<usize> {
+    let line = buffer.line(row).chars().collect::<Vec<char>>();
+    // a repeated till starts one further out, or it would find the char it is already in front of
+    let skip = if repeated && matches!(kind, Find::Till | Find::BackTill) { 2 } else { 1 };
+
+    match kind {
+        Find::To | Find::Till => {
+            let found = (col + skip..line.len()).find(|index| line[*index] == character)?;
+            return Some(if kind == Find::Till { found - 1 } else { found });
+        },
+        Find::BackTo | Find::BackTill => {
+            let found = (0..col.checked_sub(skip - 1)?).rev().find(|index| line[*index] == character)?;
+            return Some(if kind == Find::BackTill { found + 1 } else { found });
+        },
+    }
+}
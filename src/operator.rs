@@ -0,0 +1,145 @@
+use crate::buffer::Buffer;
+use crate::motion::{self, Kind, Motion, Walker};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Delete,
+    Change,
+    Yank,
+    Indent,
+    Outdent,
+    Uppercase,
+    Lowercase,
+}
+
+// what an operator works on, (row, col) in chars with an exclusive end unless the region is linewise
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+    pub linewise: bool,
+}
+
+impl Operator {
+    // d, c, > and <, y is the configured yank key and gU, gu go through the g prefix
+    pub fn from_key(char_code: i32) -> Option<Operator> {
+        return match char_code {
+            100 => Some(Operator::Delete),
+            99 => Some(Operator::Change),
+            62 => Some(Operator::Indent),
+            60 => Some(Operator::Outdent),
+            _ => None,
+        };
+    }
+
+    // the key after g that makes this operator
+    pub fn from_g_key(char_code: i32) -> Option<Operator> {
+        return match char_code {
+            85 => Some(Operator::Uppercase),
+            117 => Some(Operator::Lowercase),
+            _ => None,
+        };
+    }
+
+    // typing the last key of an operator again makes it work on whole lines, as in dd or gUU
+    pub fn line_key(self, yank: i32) -> i32 {
+        return match self {
+            Operator::Delete => 100,
+            Operator::Change => 99,
+            Operator::Yank => yank,
+            Operator::Indent => 62,
+            Operator::Outdent => 60,
+            Operator::Uppercase => 85,
+            Operator::Lowercase => 117,
+        };
+    }
+}
+
+impl Region {
+    pub fn lines(first: usize, last: usize) -> Region {
+        return Region {
+            start: (first, 0),
+            end: (last, 0),
+            linewise: true,
+        };
+    }
+
+    pub fn from_motion(buffer: &Buffer, motion: Motion, from: (usize, usize), to: (usize, usize)) -> Region {
+        let (start, end) = if from <= to { (from, to) } else { (to, from) };
+        match motion.kind() {
+            Kind::Linewise => return Region::lines(start.0, end.0),
+            Kind::Inclusive => {
+                let end = (end.0, buffer.next_grapheme(end.0, end.1).max(end.1));
+                return Region { start, end, linewise: false };
+            },
+            Kind::Exclusive => {
+                // an exclusive motion ending at the start of a later line stops at the end of the
+                // line before, and covers whole lines when it also began before the first non-blank
+                if end.1 == 0 && end.0 > start.0 {
+                    let last = end.0 - 1;
+                    if start.1 <= motion::first_non_blank(buffer, start.0) {
+                        return Region::lines(start.0, last);
+                    }
+                    return Region { start, end: (last, buffer.line_len(last)), linewise: false };
+                }
+                return Region { start, end, linewise: false };
+            },
+        }
+    }
+
+    // the text `operator` works on when given `motion`, None if the motion goes nowhere
+    pub fn for_operator(buffer: &Buffer, operator: Operator, motion: Motion, pos: (usize, usize), count: Option<usize>) -> Option<Region> {
+        let times = count.unwrap_or(1).max(1);
+        let big = match motion {
+            Motion::WordForward(big) => big,
+            Motion::Right => {
+                // unlike the cursor, dl and friends may take the last char of the line
+                let mut col = pos.1;
+                for _ in 0..times {
+                    col = buffer.next_grapheme(pos.0, col);
+                }
+                return Some(Region { start: pos, end: (pos.0, col), linewise: false });
+            },
+            _ => return Some(Region::from_motion(buffer, motion, pos, motion.target(buffer, pos, count)?)),
+        };
+
+        let on_word = motion::class(Walker::new(buffer, pos).char(), big) != 0;
+        if operator == Operator::Change && on_word {
+            // cw leaves the blanks after the word alone, like ce but without leaving a word it ends
+            let mut end = pos;
+            for index in 0..times {
+                let mut walker = Walker::new(buffer, end);
+                let at_end = !walker.forward() || walker.row != end.0 || motion::class(walker.char(), big) != motion::class(Walker::new(buffer, end).char(), big);
+                if index > 0 || !at_end {
+                    end = motion::word_end(buffer, end, big);
+                }
+            }
+            return Some(Region::from_motion(buffer, Motion::WordEnd(big), pos, end));
+        }
+
+        // a w that leaves the line only takes the text up to the end of the last word on it
+        let mut from = pos;
+        let mut to = pos;
+        for _ in 0..times {
+            from = to;
+            to = motion::word_forward(buffer, from, big);
+        }
+        if to.0 > from.0 {
+            return Some(Region { start: pos, end: (from.0, buffer.line_len(from.0)), linewise: false });
+        }
+
+        // w stops on the last char when there is no word after it, the operator still takes that char
+        let landed = Walker::new(buffer, to);
+        let mut before = Walker::new(buffer, to);
+        let stuck = !Walker::new(buffer, to).forward()
+            && (to == from || motion::class(landed.char(), big) == 0 || (before.backward() && motion::class(before.char(), big) == motion::class(landed.char(), big)));
+        if stuck {
+            return Some(Region { start: pos, end: (to.0, buffer.line_len(to.0)), linewise: false });
+        }
+        return Some(Region::from_motion(buffer, motion, pos, to));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return !self.linewise && self.start >= self.end;
+    }
+}
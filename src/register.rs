@@ -0,0 +1,138 @@
+use crate::clipboard::Clipboard;
+use crate::editor::Shape;
+
+// yanked or deleted text along with how it was taken, which decides how it is put back
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Register {
+    pub text: String,
+    pub shape: Shape,
+}
+
+impl Register {
+    pub fn new(text: String, shape: Shape) -> Register {
+        return Register { text, shape };
+    }
+
+    // "A appends to "a, anything appended to or from whole lines makes whole lines
+    fn append(&mut self, other: Register) {
+        if self.shape == Shape::Line || other.shape == Shape::Line {
+            self.text = format!("{}\n{}", self.text, other.text);
+            self.shape = Shape::Line;
+        } else {
+            self.text.push_str(&other.text);
+        }
+    }
+}
+
+// every register the editor knows, named by the char typed after "
+#[derive(Debug)]
+pub struct Registers {
+    // "" always holds what was yanked or deleted last
+    unnamed: Option<Register>,
+    // "0 is the last yank, "1 to "9 the last deletes, newest first
+    numbered: [Option<Register>; 10],
+    named: [Option<Register>; 26],
+    // "+ and "*, which are the same register here
+    pub clipboard: Clipboard,
+}
+
+impl Registers {
+    pub fn new(clipboard: Clipboard) -> Registers {
+        return Registers {
+            unnamed: None,
+            numbered: Default::default(),
+            named: Default::default(),
+            clipboard,
+        };
+    }
+
+    // whether `name` can follow ", the black hole _ included
+    pub fn is_valid(name: char) -> bool {
+        return "\"_+*".contains(name) || name.is_ascii_alphanumeric();
+    }
+
+    // like get, but "+ and "* ask the system clipboard
+    pub fn read(&mut self, name: char) -> Result<Option<Register>, String> {
+        if name == '+' || name == '*' {
+            return self.clipboard.paste();
+        }
+        return Ok(self.get(name).cloned());
+    }
+
+    pub fn get(&self, name: char) -> Option<&Register> {
+        return match name {
+            '"' => self.unnamed.as_ref(),
+            '0'..='9' => self.numbered[name as usize - '0' as usize].as_ref(),
+            'a'..='z' | 'A'..='Z' => self.named[name.to_ascii_lowercase() as usize - 'a' as usize].as_ref(),
+            _ => None,
+        };
+    }
+
+    // a yank goes to "0 unless a register was named
+    pub fn yank(&mut self, name: Option<char>, register: Register) -> Result<(), String> {
+        match name {
+            None | Some('"') => {
+                self.numbered[0] = Some(register.clone());
+                self.unnamed = Some(register);
+                return Ok(());
+            },
+            Some(name) => return self.store(name, register),
+        }
+    }
+
+    // a delete shifts "1 to "9 along unless a register was named
+    pub fn delete(&mut self, name: Option<char>, register: Register) -> Result<(), String> {
+        match name {
+            None | Some('"') => {
+                self.numbered[1..].rotate_right(1);
+                self.numbered[1] = Some(register.clone());
+                self.unnamed = Some(register);
+                return Ok(());
+            },
+            Some(name) => return self.store(name, register),
+        }
+    }
+
+    fn store(&mut self, name: char, register: Register) -> Result<(), String> {
+        if name == '+' || name == '*' {
+            self.unnamed = Some(register.clone());
+            return self.clipboard.copy(register);
+        }
+
+        let slot = match name {
+            'a'..='z' | 'A'..='Z' => &mut self.named[name.to_ascii_lowercase() as usize - 'a' as usize],
+            '0'..='9' => &mut self.numbered[name as usize - '0' as usize],
+            // the black hole swallows the text and leaves "" alone
+            _ => return Ok(()),
+        };
+
+        match slot {
+            Some(existing) if name.is_ascii_uppercase() => existing.append(register),
+            _ => *slot = Some(register),
+        }
+        self.unnamed = slot.clone();
+        return Ok(());
+    }
+
+    // what :registers shows, one register a line with line breaks as ^J
+    pub fn list(&self) -> String {
+        let mut lines = vec![String::from("Type Name Content")];
+        let names = std::iter::once('"').chain('0'..='9').chain('a'..='z').chain(std::iter::once('+'));
+        for name in names {
+            let register = if name == '+' { self.clipboard.last() } else { self.get(name) };
+            if let Some(register) = register {
+                let kind = match register.shape {
+                    Shape::Char => 'c',
+                    Shape::Line => 'l',
+                    Shape::Block => 'b',
+                };
+                let mut text = register.text.replace('\n', "^J");
+                if register.shape == Shape::Line {
+                    text.push_str("^J");
+                }
+                lines.push(format!("  {}  \"{}   {}", kind, name, text));
+            }
+        }
+        return lines.join("\n");
+    }
+}
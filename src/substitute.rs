@@ -0,0 +1,300 @@
+use std::ops::RangeInclusive;
+
+use regex::{Captures, Regex, RegexBuilder};
+
+use crate::buffer::Buffer;
+use crate::motion::first_non_blank;
+use crate::Window;
+
+// the rows a range is worked out from
+pub struct Lines {
+    pub current: usize,
+    pub last: usize,
+    // first and last row of the last visual selection, for '< and '>
+    pub visual: (usize, usize),
+}
+
+// :s/pattern/replacement/flags
+#[derive(Debug)]
+pub struct Substitute {
+    pub pattern: Regex,
+    pub replacement: String,
+    // every match on a line instead of just the first
+    pub global: bool,
+    pub confirm: bool,
+}
+
+// the lines in front of a command such as :%s, :10,20s or :'<,'>s along with the command after them,
+// no lines given means the cursor line
+pub fn range<'a>(text: &'a str, lines: &Lines) -> Result<(RangeInclusive<usize>, &'a str), String> {
+    if let Some(rest) = text.strip_prefix('%') {
+        return Ok((0..=lines.last, rest));
+    }
+
+    let (first, rest) = address(text, lines)?;
+    let first = match first {
+        Some(first) => first,
+        None => return Ok((lines.current..=lines.current, rest)),
+    };
+    let (second, rest) = match rest.strip_prefix(',') {
+        Some(rest) => {
+            let (second, rest) = address(rest, lines)?;
+            (second.unwrap_or(lines.current), rest)
+        },
+        None => (first, rest),
+    };
+
+    if first.max(second) > lines.last {
+        return Err(String::from("Invalid range"));
+    }
+    return Ok((first.min(second)..=first.max(second), rest));
+}
+
+// a line number counted from 1 like on screen, . for the cursor line, $ for the last one
+// and '< or '> for the first or last line of the last selection
+fn address<'a>(text: &'a str, lines: &Lines) -> Result<(Option<usize>, &'a str), String> {
+    let digits = text.chars().take_while(char::is_ascii_digit).count();
+    if digits > 0 {
+        let number = text[..digits].parse::<usize>().map_err(|_| String::from("Invalid range"))?;
+        return Ok((Some(number.saturating_sub(1)), &text[digits..]));
+    }
+
+    let addresses = [(".", lines.current), ("$", lines.last), ("'<", lines.visual.0), ("'>", lines.visual.1)];
+    for (prefix, row) in addresses {
+        if let Some(rest) = text.strip_prefix(prefix) {
+            return Ok((Some(row), rest));
+        }
+    }
+    return Ok((None, text));
+}
+
+// the text up to the first `delimiter` that has no backslash before it and what follows that delimiter,
+// a backslashed delimiter loses its backslash and every other backslash is kept
+fn split(text: &str, delimiter: char) -> (String, Option<&str>) {
+    let mut part = String::new();
+    let mut chars = text.char_indices();
+    while let Some((byte, character)) = chars.next() {
+        if character == delimiter {
+            return (part, Some(&text[byte + character.len_utf8()..]));
+        }
+        if character == '\\' {
+            match chars.next() {
+                Some((_, escaped)) if escaped == delimiter => part.push(escaped),
+                Some((_, escaped)) => {
+                    part.push('\\');
+                    part.push(escaped);
+                },
+                None => part.push('\\'),
+            }
+            continue;
+        }
+        part.push(character);
+    }
+    return (part, None);
+}
+
+impl Substitute {
+    // `text` is the command with its range taken off, None when it is not a :s at all,
+    // an empty pattern searches for `last` again
+    pub fn parse(text: &str, last: Option<&Regex>) -> Option<Result<Substitute, String>> {
+        let body = text.strip_prefix("substitute").or_else(|| text.strip_prefix('s'))?;
+        let delimiter = body.chars().next()?;
+        if delimiter.is_alphanumeric() || delimiter.is_whitespace() || delimiter == '\\' || delimiter == '"' {
+            return None;
+        }
+
+        let (pattern, rest) = split(&body[delimiter.len_utf8()..], delimiter);
+        let (replacement, flags) = match rest {
+            Some(rest) => {
+                let (replacement, flags) = split(rest, delimiter);
+                (replacement, flags.unwrap_or(""))
+            },
+            None => (String::new(), ""),
+        };
+        return Some(Substitute::new(&pattern, replacement, flags, last));
+    }
+
+    fn new(pattern: &str, replacement: String, flags: &str, last: Option<&Regex>) -> Result<Substitute, String> {
+        let (mut global, mut ignore_case, mut confirm) = (false, false, false);
+        for flag in flags.chars() {
+            match flag {
+                'g' => global = true,
+                'i' => ignore_case = true,
+                'c' => confirm = true,
+                _ => return Err(format!("Trailing characters: {}", flags)),
+            }
+        }
+
+        let pattern = match last {
+            Some(last) if pattern.is_empty() => last.as_str(),
+            None if pattern.is_empty() => return Err(String::from("No previous regular expression")),
+            _ => pattern,
+        };
+        let pattern = RegexBuilder::new(pattern).case_insensitive(ignore_case).build().map_err(|err| err.to_string())?;
+        return Ok(Substitute { pattern, replacement, global, confirm });
+    }
+
+    // the replacement for one match, & and \0 stand for the whole match and \1 to \9 for its groups
+    pub fn expand(&self, captures: &Captures) -> String {
+        let mut text = String::new();
+        let mut chars = self.replacement.chars();
+        while let Some(character) = chars.next() {
+            match character {
+                '&' => text.push_str(&captures[0]),
+                '\\' => match chars.next() {
+                    Some(digit @ '0'..='9') => {
+                        let group = digit as usize - '0' as usize;
+                        text.push_str(captures.get(group).map_or("", |found| found.as_str()));
+                    },
+                    // \r and \n break the line there and \t is a tab
+                    Some('r' | 'n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    // \& and \\ are the char itself
+                    Some(escaped) => text.push(escaped),
+                    None => text.push('\\'),
+                },
+                _ => text.push(character),
+            }
+        }
+        return text;
+    }
+}
+
+// a :s on its way through its lines, it waits here for an answer while the c flag asks about a match
+pub struct Substitution {
+    pub substitute: Substitute,
+    row: usize,
+    col: usize,
+    last: usize,
+    // cleared by a, which replaces the rest without asking
+    ask: bool,
+    found: usize,
+    replaced: usize,
+    lines: usize,
+    // the last row something was replaced on, where the cursor ends up
+    changed: Option<usize>,
+    // col is right after a match, where an empty match does not count, as in s/o*/-/g
+    after_match: bool,
+}
+
+impl Substitution {
+    pub fn new(substitute: Substitute, rows: RangeInclusive<usize>) -> Substitution {
+        return Substitution {
+            ask: substitute.confirm,
+            substitute,
+            row: *rows.start(),
+            col: 0,
+            last: *rows.end(),
+            found: 0,
+            replaced: 0,
+            lines: 0,
+            changed: None,
+            after_match: false,
+        };
+    }
+
+    // replaces matches until one has to be asked about, which is when it returns true with the cursor on it,
+    // `answer` is the y, n, a, l or q typed for the match asked about last
+    pub fn run(&mut self, buffer: &mut Buffer, window: &mut Window, mut answer: Option<char>) -> bool {
+        while self.row <= self.last {
+            let line = buffer.line(self.row);
+            let byte = line.char_indices().nth(self.col).map_or(line.len(), |(byte, _)| byte);
+            let captures = match self.substitute.pattern.captures_at(&line, byte) {
+                Some(captures) if self.col <= line.chars().count() => captures,
+                _ => {
+                    self.next_row();
+                    continue;
+                },
+            };
+            let (start, len) = match captures.get(0) {
+                Some(found) => (line[..found.start()].chars().count(), found.as_str().chars().count()),
+                None => break,
+            };
+            if len == 0 && start == self.col && self.after_match {
+                self.col += 1;
+                self.after_match = false;
+                continue;
+            }
+            self.col = start;
+            // a match that was asked about comes round again with the answer
+            if answer.is_none() {
+                self.found += 1;
+            }
+
+            let (replace, stop) = if self.ask {
+                match answer.take() {
+                    Some('y') => (true, false),
+                    Some('n') => (false, false),
+                    Some('a') => {
+                        self.ask = false;
+                        (true, false)
+                    },
+                    Some('l') => (true, true),
+                    Some('q' | '\x1b') => (false, true),
+                    _ => {
+                        window.restore_cursor(buffer, (self.row as i32, start as i32));
+                        return true;
+                    },
+                }
+            } else {
+                (true, false)
+            };
+
+            let mut resume = start + len;
+            if replace {
+                let text = self.substitute.expand(&captures);
+                let idx = buffer.char_idx(self.row as i32, start as i32);
+                if len > 0 {
+                    buffer.remove_text(idx..idx + len);
+                }
+                if !text.is_empty() {
+                    buffer.insert_text(idx, &text);
+                }
+                self.replaced += 1;
+                if self.changed != Some(self.row) {
+                    self.lines += 1;
+                }
+                // a replacement that breaks the line carries on from the last line it made
+                let breaks = text.matches('\n').count();
+                self.row += breaks;
+                self.last += breaks;
+                self.changed = Some(self.row);
+                resume = match text.rsplit_once('\n') {
+                    Some((_, tail)) => tail.chars().count(),
+                    None => start + text.chars().count(),
+                };
+            }
+
+            if stop {
+                break;
+            } else if !self.substitute.global {
+                self.next_row();
+            } else {
+                // an empty match steps over a char so it is not found again right away
+                self.col = resume + if len == 0 { 1 } else { 0 };
+                self.after_match = len > 0;
+            }
+        }
+
+        self.row = self.last + 1;
+        if let Some(row) = self.changed {
+            window.restore_cursor(buffer, (row as i32, first_non_blank(buffer, row) as i32));
+        }
+        return false;
+    }
+
+    fn next_row(&mut self) {
+        self.row += 1;
+        self.col = 0;
+        self.after_match = false;
+    }
+
+    // what is shown once the substitution is over
+    pub fn report(&self) -> String {
+        if self.found == 0 {
+            return format!("Pattern not found: {}", self.substitute.pattern.as_str());
+        }
+        let plural = |count: usize| if count == 1 { "" } else { "s" };
+        return format!("{} substitution{} on {} line{}", self.replaced, plural(self.replaced), self.lines, plural(self.lines));
+    }
+}
@@ -0,0 +1,708 @@
+#[cfg(feature = "tree-sitter")]
+pub mod treesitter;
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::buffer::Buffer;
+
+// definitions shipped with zin, user definitions with the same name take precedence
+const BUNDLED: [(&str, &str); 7] = [
+    ("rust.toml", include_str!("../syntax/rust.toml")),
+    ("python.toml", include_str!("../syntax/python.toml")),
+    ("c.toml", include_str!("../syntax/c.toml")),
+    ("toml.toml", include_str!("../syntax/toml.toml")),
+    ("yaml.toml", include_str!("../syntax/yaml.toml")),
+    ("sh.toml", include_str!("../syntax/sh.toml")),
+    ("markdown.toml", include_str!("../syntax/markdown.toml")),
+];
+
+const DEFAULT_SYMBOLS: &str = " \t.,;:(){}[]<>+-*/%=!&|^~?@#$\"'`\\";
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SyntaxModes {
+    Str(usize),
+    MultiLineStr(usize),
+    RawStr(usize),
+    Normal,
+    Comment,
+    DocComment,
+    BlockComment(usize, usize),
+}
+
+#[derive(Debug, Clone)]
+pub struct Syntax {
+    pub name: String,
+    extensions: Vec<String>,
+    filenames: Vec<String>,
+    shebangs: Vec<String>,
+    keywords: Vec<String>,
+    symbols: Vec<String>,
+    types: Vec<String>,
+    operators: Vec<String>,
+    strings: Vec<String>,
+    multiline_strings: Vec<String>,
+    raw_strings: Vec<String>,
+    comments: Vec<String>,
+    doc_comments: Vec<String>,
+    block_comments: Vec<(String, String)>,
+    nested_comments: bool,
+    escape: Option<char>,
+    numbers: bool,
+    char_literals: bool,
+    lifetimes: bool,
+    macros: Option<String>,
+    attributes: Vec<String>,
+    states: Vec<SyntaxModes>,
+    #[cfg(feature = "tree-sitter")]
+    tree_sitter: Option<treesitter::TreeSitter>,
+}
+
+#[derive(Debug)]
+pub struct Registry {
+    syntaxes: Vec<Syntax>,
+}
+
+fn string_list(value: &toml::Value, key: &str) -> Result<Vec<String>, String> {
+    let error = || format!("`{}` must be a list of strings", key);
+    let mut list = Vec::new();
+    for item in value.as_array().ok_or_else(error)? {
+        list.push(item.as_str().ok_or_else(error)?.to_string());
+    }
+    return Ok(list);
+}
+
+// "#!/usr/bin/env python3 -u" and "#!/bin/python3" both name python3
+fn interpreter(first_line: &str) -> Option<&str> {
+    let mut words = first_line.strip_prefix("#!")?.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        program = words.find(|word| !word.starts_with('-'))?;
+    }
+    return Some(program);
+}
+
+impl Syntax {
+    pub fn empty() -> Syntax {
+        Syntax {
+            name: String::from("text"),
+            extensions: Vec::new(),
+            filenames: Vec::new(),
+            shebangs: Vec::new(),
+            keywords: Vec::new(),
+            symbols: Vec::new(),
+            types: Vec::new(),
+            operators: Vec::new(),
+            strings: Vec::new(),
+            multiline_strings: Vec::new(),
+            raw_strings: Vec::new(),
+            comments: Vec::new(),
+            doc_comments: Vec::new(),
+            block_comments: Vec::new(),
+            nested_comments: false,
+            escape: None,
+            numbers: false,
+            char_literals: false,
+            lifetimes: false,
+            macros: None,
+            attributes: Vec::new(),
+            states: Vec::new(),
+            #[cfg(feature = "tree-sitter")]
+            tree_sitter: None,
+        }
+    }
+
+    pub fn parse(contents: &str) -> Result<Syntax, String> {
+        let table = contents.parse::<toml::Table>().map_err(|err| err.to_string())?;
+        let mut syntax = Self::empty();
+        syntax.symbols = DEFAULT_SYMBOLS.chars().map(String::from).collect();
+
+        for (key, value) in &table {
+            match key.as_str() {
+                "name" => {
+                    syntax.name = value.as_str().ok_or("`name` must be a string")?.to_string();
+                },
+                "extensions" => syntax.extensions = string_list(value, key)?,
+                "filenames" => syntax.filenames = string_list(value, key)?,
+                "shebangs" => syntax.shebangs = string_list(value, key)?,
+                "keywords" => syntax.keywords = string_list(value, key)?,
+                "symbols" => syntax.symbols = string_list(value, key)?,
+                "types" => syntax.types = string_list(value, key)?,
+                "operators" => syntax.operators = string_list(value, key)?,
+                "strings" => syntax.strings = string_list(value, key)?,
+                "multiline_strings" => syntax.multiline_strings = string_list(value, key)?,
+                "raw_strings" => syntax.raw_strings = string_list(value, key)?,
+                "comments" => syntax.comments = string_list(value, key)?,
+                "doc_comments" => syntax.doc_comments = string_list(value, key)?,
+                "attributes" => syntax.attributes = string_list(value, key)?,
+                "escape" => {
+                    let mut chars = value.as_str().ok_or("`escape` must be a string")?.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(escape), None) => syntax.escape = Some(escape),
+                        _ => return Err(String::from("`escape` must be a single character")),
+                    }
+                },
+                "macros" => {
+                    syntax.macros = Some(value.as_str().ok_or("`macros` must be a string")?.to_string());
+                },
+                "numbers" => syntax.numbers = value.as_bool().ok_or("`numbers` must be true or false")?,
+                "char_literals" => syntax.char_literals = value.as_bool().ok_or("`char_literals` must be true or false")?,
+                "lifetimes" => syntax.lifetimes = value.as_bool().ok_or("`lifetimes` must be true or false")?,
+                "block_comments" => {
+                    let error = "`block_comments` must be a list of [open, close] pairs";
+                    for pair in value.as_array().ok_or(error)? {
+                        match string_list(pair, key).as_deref() {
+                            Ok([open, close]) => syntax.block_comments.push((open.clone(), close.clone())),
+                            _ => return Err(String::from(error)),
+                        }
+                    }
+                },
+                "nested_comments" => {
+                    syntax.nested_comments = value.as_bool().ok_or("`nested_comments` must be true or false")?;
+                },
+                _ => return Err(format!("unknown field `{}`", key)),
+            }
+        }
+
+        let block_comments = syntax.block_comments.iter().flat_map(|(open, close)| [open, close]);
+        let empty = syntax.strings.iter()
+            .chain(&syntax.multiline_strings)
+            .chain(&syntax.raw_strings)
+            .chain(&syntax.comments)
+            .chain(&syntax.doc_comments)
+            .chain(&syntax.attributes)
+            .chain(block_comments)
+            .any(|marker| marker.is_empty());
+        if empty {
+            return Err(String::from("string and comment markers can't be empty"));
+        }
+        return Ok(syntax);
+    }
+
+    fn matches_filename(&self, filename: &str) -> bool {
+        return self.filenames.iter().any(|name| name == filename);
+    }
+
+    fn matches_extension(&self, extension: &str) -> bool {
+        return self.extensions.iter().any(|name| name == extension);
+    }
+
+    fn matches_interpreter(&self, program: &str) -> bool {
+        return self.shebangs.iter().any(|name| {
+            // allow versioned interpreters such as python3.11
+            program.strip_prefix(name.as_str())
+                .is_some_and(|version| version.chars().all(|c| c.is_ascii_digit() || c == '.'))
+        });
+    }
+
+    fn starts_with_at(line: &[char], index: usize, marker: &str) -> bool {
+        for (offset, character) in (index..).zip(marker.chars()) {
+            if line.get(offset) != Some(&character) {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    fn color_of_token(&self, token: String) -> (i16, String) {
+        if self.keywords.contains(&token) {
+            return (5, token);
+        } else if self.types.contains(&token) {
+            return (6, token);
+        } else if self.operators.contains(&token) {
+            return (7, token);
+        } else {
+            return (1, token);
+        }
+    }
+
+    fn flush(&self, mode: &SyntaxModes, token: &mut String, highlighted: &mut Vec<(i16, String)>) {
+        if !token.is_empty() {
+            let token = std::mem::take(token);
+            highlighted.push(match mode {
+                SyntaxModes::Normal => self.color_of_token(token),
+                SyntaxModes::Str(_) | SyntaxModes::MultiLineStr(_) | SyntaxModes::RawStr(_) => (8, token),
+                SyntaxModes::Comment | SyntaxModes::BlockComment(..) => (9, token),
+                SyntaxModes::DocComment => (15, token),
+            });
+        }
+    }
+
+    // 'a', '\n' and '\u{1F600}' are char literals, a quote followed by a bare word is a lifetime
+    fn quote_at(&self, line: &[char], index: usize) -> Option<(usize, i16)> {
+        if line[index] != '\'' {
+            return None;
+        }
+
+        if self.char_literals {
+            let end = if self.escape.is_some() && line.get(index + 1).copied() == self.escape {
+                line.get(index + 3..)?.iter().position(|character| *character == '\'').map(|offset| index + 3 + offset)
+            } else if line.get(index + 2) == Some(&'\'') {
+                Some(index + 2)
+            } else {
+                None
+            };
+            if let Some(end) = end {
+                return Some((end + 1 - index, 11));
+            }
+        }
+
+        if self.lifetimes {
+            let len = line[index + 1..].iter().take_while(|character| character.is_alphanumeric() || **character == '_').count();
+            if len != 0 {
+                return Some((len + 1, 12));
+            }
+        }
+        return None;
+    }
+
+    // 0xff, 1_000u32 and 1.5e-3 are all one literal, `index` holds the leading digit
+    fn number_len(line: &[char], index: usize) -> usize {
+        let hex = line[index] == '0' && matches!(line.get(index + 1), Some('x' | 'X'));
+        let mut end = index;
+        while let Some(character) = line.get(end) {
+            let exponent = !hex && matches!(character, '+' | '-') && matches!(line[end - 1], 'e' | 'E');
+            let fraction = *character == '.' && line.get(end + 1).is_some_and(|next| next.is_ascii_digit());
+            if character.is_ascii_alphanumeric() || *character == '_' || exponent || fraction {
+                end += 1;
+            } else {
+                break;
+            }
+        }
+        return end - index;
+    }
+
+    // runs to the bracket closing the one opened by the marker, or to the end of the line
+    fn attribute_len(line: &[char], index: usize) -> usize {
+        let mut depth = 0;
+        for (offset, character) in line[index..].iter().enumerate() {
+            if *character == '[' {
+                depth += 1;
+            } else if *character == ']' {
+                depth -= 1;
+                if depth == 0 {
+                    return offset + 1;
+                }
+            }
+        }
+        return line.len() - index;
+    }
+
+    // matches an opener such as r#" or br##" and returns its length and number of hashes
+    fn raw_string_at(&self, line: &[char], index: usize) -> Option<(usize, usize)> {
+        for prefix in &self.raw_strings {
+            if Self::starts_with_at(line, index, prefix) {
+                let start = index + prefix.chars().count();
+                let hashes = line[start..].iter().take_while(|character| **character == '#').count();
+                if line.get(start + hashes) == Some(&'"') {
+                    return Some((start + hashes + 1 - index, hashes));
+                }
+            }
+        }
+        return None;
+    }
+
+    // highlights one line starting in `mode` and returns the mode the next line starts in
+    fn scan(&self, line: &str, mut mode: SyntaxModes) -> (Vec<(i16, String)>, SyntaxModes) {
+        let mut token = String::new();
+        let mut highlighted: Vec<(i16, String)> = Vec::new();
+
+        let line: Vec<char> = line.chars().collect();
+        let mut index = 0;
+        while index < line.len() {
+            match mode {
+                SyntaxModes::Normal => {
+                    if let Some(pair) = self.block_comments.iter().position(|(open, _)| Self::starts_with_at(&line, index, open)) {
+                        self.flush(&mode, &mut token, &mut highlighted);
+                        mode = SyntaxModes::BlockComment(pair, 1);
+                        token.push_str(&self.block_comments[pair].0);
+                        index += self.block_comments[pair].0.chars().count();
+                        continue;
+                    }
+
+                    if self.doc_comments.iter().any(|marker| Self::starts_with_at(&line, index, marker)) {
+                        self.flush(&mode, &mut token, &mut highlighted);
+                        mode = SyntaxModes::DocComment;
+                        continue;
+                    }
+
+                    if self.comments.iter().any(|marker| Self::starts_with_at(&line, index, marker)) {
+                        self.flush(&mode, &mut token, &mut highlighted);
+                        mode = SyntaxModes::Comment;
+                        continue;
+                    }
+
+                    if token.is_empty() {
+                        if let Some((len, hashes)) = self.raw_string_at(&line, index) {
+                            mode = SyntaxModes::RawStr(hashes);
+                            token.extend(&line[index..index + len]);
+                            index += len;
+                            continue;
+                        }
+
+                        let literal = if self.attributes.iter().any(|marker| Self::starts_with_at(&line, index, marker)) {
+                            Some((Self::attribute_len(&line, index), 14))
+                        } else if self.numbers && line[index].is_ascii_digit() {
+                            Some((Self::number_len(&line, index), 10))
+                        } else {
+                            self.quote_at(&line, index)
+                        };
+                        if let Some((len, color)) = literal {
+                            highlighted.push((color, line[index..index + len].iter().collect()));
+                            index += len;
+                            continue;
+                        }
+                    }
+
+                    if let Some(delimiter) = self.multiline_strings.iter().position(|marker| Self::starts_with_at(&line, index, marker)) {
+                        self.flush(&mode, &mut token, &mut highlighted);
+                        mode = SyntaxModes::MultiLineStr(delimiter);
+                        token.push_str(&self.multiline_strings[delimiter]);
+                        index += self.multiline_strings[delimiter].chars().count();
+                        continue;
+                    }
+
+                    if let Some(delimiter) = self.strings.iter().position(|marker| Self::starts_with_at(&line, index, marker)) {
+                        self.flush(&mode, &mut token, &mut highlighted);
+                        mode = SyntaxModes::Str(delimiter);
+                        token.push_str(&self.strings[delimiter]);
+                        index += self.strings[delimiter].chars().count();
+                        continue;
+                    }
+
+                    let character = line[index].to_string();
+                    let identifier = !token.is_empty() && token.chars().all(|c| c.is_alphanumeric() || c == '_');
+                    // `x!=y` compares x, it doesn't call a macro
+                    let compares = line.get(index + 1) == Some(&'=');
+                    if identifier && !compares && self.macros.as_ref() == Some(&character) {
+                        token.push_str(&character);
+                        highlighted.push((13, std::mem::take(&mut token)));
+                    } else if self.symbols.contains(&character) {
+                        self.flush(&mode, &mut token, &mut highlighted);
+                        highlighted.push(self.color_of_token(character));
+                    } else {
+                        token.push(line[index]);
+                    }
+                    index += 1;
+                },
+                SyntaxModes::Str(delimiter) | SyntaxModes::MultiLineStr(delimiter) => {
+                    if self.escape == Some(line[index]) && index + 1 < line.len() {
+                        token.extend(&line[index..index + 2]);
+                        index += 2;
+                        continue;
+                    }
+
+                    let marker = match mode {
+                        SyntaxModes::Str(_) => &self.strings[delimiter],
+                        _ => &self.multiline_strings[delimiter],
+                    };
+                    if Self::starts_with_at(&line, index, marker) {
+                        token.push_str(marker);
+                        index += marker.chars().count();
+                        self.flush(&mode, &mut token, &mut highlighted);
+                        mode = SyntaxModes::Normal;
+                    } else {
+                        token.push(line[index]);
+                        index += 1;
+                    }
+                },
+                SyntaxModes::RawStr(hashes) => {
+                    let closes = line[index] == '"'
+                        && line[index + 1..].iter().take(hashes).filter(|character| **character == '#').count() == hashes;
+                    if closes {
+                        token.extend(&line[index..index + 1 + hashes]);
+                        index += 1 + hashes;
+                        self.flush(&mode, &mut token, &mut highlighted);
+                        mode = SyntaxModes::Normal;
+                    } else {
+                        token.push(line[index]);
+                        index += 1;
+                    }
+                },
+                SyntaxModes::BlockComment(pair, depth) => {
+                    let (open, close) = &self.block_comments[pair];
+                    if Self::starts_with_at(&line, index, close) {
+                        token.push_str(close);
+                        index += close.chars().count();
+                        if depth == 1 {
+                            self.flush(&mode, &mut token, &mut highlighted);
+                            mode = SyntaxModes::Normal;
+                        } else {
+                            mode = SyntaxModes::BlockComment(pair, depth - 1);
+                        }
+                    } else if self.nested_comments && Self::starts_with_at(&line, index, open) {
+                        token.push_str(open);
+                        index += open.chars().count();
+                        mode = SyntaxModes::BlockComment(pair, depth + 1);
+                    } else {
+                        token.push(line[index]);
+                        index += 1;
+                    }
+                },
+                SyntaxModes::Comment | SyntaxModes::DocComment => {
+                    token.extend(&line[index..]);
+                    index = line.len();
+                },
+            }
+        }
+        self.flush(&mode, &mut token, &mut highlighted);
+
+        // line comments and plain strings never continue onto the next line
+        let mode = match mode {
+            SyntaxModes::Comment | SyntaxModes::DocComment | SyntaxModes::Str(_) => SyntaxModes::Normal,
+            mode => mode,
+        };
+        return (highlighted, mode);
+    }
+
+    // every state before `row` stays valid, the line itself starts in the same state as before
+    fn invalidate(&mut self, row: usize) {
+        self.states.truncate(row + 1);
+    }
+
+    // catches up with the edits made to the buffer since the last redraw
+    pub fn update(&mut self, buffer: &mut Buffer) {
+        let changes = buffer.take_changes();
+        if let Some(row) = changes.iter().map(|change| change.start.0).min() {
+            self.invalidate(row);
+        }
+
+        #[cfg(feature = "tree-sitter")]
+        if let Some(tree_sitter) = &mut self.tree_sitter {
+            tree_sitter.update(buffer, &changes);
+        }
+    }
+
+    pub fn highlight_line(&mut self, buffer: &Buffer, row: usize) -> Vec<(i16, String)> {
+        #[cfg(feature = "tree-sitter")]
+        if let Some(tree_sitter) = &self.tree_sitter {
+            return tree_sitter.highlight_line(buffer, row);
+        }
+
+        if self.states.is_empty() {
+            self.states.push(SyntaxModes::Normal);
+        }
+
+        while self.states.len() <= row {
+            let last = self.states.len() - 1;
+            let (_, mode) = self.scan(&buffer.line(last), self.states[last].clone());
+            self.states.push(mode);
+        }
+
+        let (highlighted, mode) = self.scan(&buffer.line(row), self.states[row].clone());
+        if self.states.len() == row + 1 {
+            self.states.push(mode);
+        }
+        return highlighted;
+    }
+}
+
+impl Registry {
+    fn directory() -> Option<PathBuf> {
+        let config = match env::var_os("XDG_CONFIG_HOME") {
+            Some(config) if !config.is_empty() => PathBuf::from(config),
+            _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+        };
+        return Some(config.join("zin").join("syntax"));
+    }
+
+    // bundled definitions first, then every *.toml in $XDG_CONFIG_HOME/zin/syntax
+    pub fn load() -> Result<Registry, String> {
+        let mut registry = Registry {
+            syntaxes: Vec::new(),
+        };
+
+        for (name, contents) in BUNDLED {
+            registry.add(Syntax::parse(contents).map_err(|err| format!("{}: {}", name, err))?);
+        }
+
+        let entries = match Self::directory().map(fs::read_dir) {
+            Some(Ok(entries)) => entries,
+            _ => return Ok(registry),
+        };
+
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == "toml"))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let contents = fs::read_to_string(&path).map_err(|err| format!("{}: {}", path.display(), err))?;
+            registry.add(Syntax::parse(&contents).map_err(|err| format!("{}: {}", path.display(), err))?);
+        }
+        return Ok(registry);
+    }
+
+    fn add(&mut self, syntax: Syntax) {
+        self.syntaxes.retain(|existing| existing.name != syntax.name);
+        self.syntaxes.push(syntax);
+    }
+
+    // picks by file name first, then extension, then the interpreter named on the first line
+    pub fn select(&self, filepath: &str, first_line: &str) -> Syntax {
+        let path = Path::new(filepath);
+        let filename = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+        let extension = path.extension().map(|extension| extension.to_string_lossy()).unwrap_or_default();
+
+        let found = self.syntaxes.iter().rev()
+            .find(|syntax| syntax.matches_filename(&filename))
+            .or_else(|| self.syntaxes.iter().rev().find(|syntax| !extension.is_empty() && syntax.matches_extension(&extension)))
+            .or_else(|| {
+                let program = interpreter(first_line)?;
+                self.syntaxes.iter().rev().find(|syntax| syntax.matches_interpreter(program))
+            });
+
+        #[allow(unused_mut)]
+        let mut syntax = match found {
+            Some(syntax) => syntax.clone(),
+            None => Syntax::empty(),
+        };
+
+        // a compiled grammar takes over from the keyword highlighter when there is one
+        #[cfg(feature = "tree-sitter")]
+        {
+            syntax.tree_sitter = treesitter::TreeSitter::new(&syntax.name);
+        }
+        return syntax;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process;
+    use super::*;
+
+    // only what ships with zin, whatever is in the user's config directory
+    fn bundled() -> Registry {
+        let mut registry = Registry { syntaxes: Vec::new() };
+        for (_, contents) in BUNDLED {
+            registry.add(Syntax::parse(contents).unwrap());
+        }
+        return registry;
+    }
+
+    // parsed directly so the keyword highlighter runs even with a tree-sitter grammar compiled in
+    fn bundled_syntax(name: &str) -> Syntax {
+        let (_, contents) = BUNDLED.iter().find(|(file, _)| *file == format!("{}.toml", name)).unwrap();
+        return Syntax::parse(contents).unwrap();
+    }
+
+    // every test gets its own file so they can run in parallel
+    fn buffer(name: &str, contents: &str) -> Buffer {
+        let dir = env::temp_dir().join(format!("zin-test-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        return Buffer::new(&path.to_string_lossy()).unwrap();
+    }
+
+    // the tokens of every row with the blanks between them left out
+    fn highlighted(syntax: &mut Syntax, buffer: &Buffer) -> Vec<Vec<(i16, String)>> {
+        let mut rows = Vec::new();
+        for row in 0..buffer.len_lines() {
+            let tokens = syntax.highlight_line(buffer, row);
+            rows.push(tokens.into_iter().filter(|(_, token)| !token.trim().is_empty()).collect());
+        }
+        return rows;
+    }
+
+    fn tokens(list: &[(i16, &str)]) -> Vec<(i16, String)> {
+        return list.iter().map(|(color, token)| (*color, token.to_string())).collect();
+    }
+
+    #[test]
+    fn syntax_is_detected() {
+        let registry = bundled();
+        let name = |filepath: &str, first_line: &str| registry.select(filepath, first_line).name;
+        assert_eq!(name("src/main.rs", ""), "rust");
+        assert_eq!(name("/home/me/.bashrc", ""), "sh");
+        assert_eq!(name("Cargo.lock", ""), "toml");
+        assert_eq!(name("run", "#!/usr/bin/env -S python3 -u"), "python");
+        assert_eq!(name("run", "#!/usr/bin/python3.11"), "python");
+        assert_eq!(name("run", "#!/bin/bash -e"), "sh");
+        assert_eq!(name("setup.py", "#!/bin/sh"), "python");
+        assert_eq!(name("notes.txt", ""), "text");
+        assert_eq!(name("run", "#!/usr/bin/pythonista"), "text");
+    }
+
+    #[test]
+    fn tokens_are_colored() {
+        let rust = buffer("tokens.rs", "let s: String = \"a b\" * 2; // hi");
+        assert_eq!(highlighted(&mut bundled_syntax("rust"), &rust)[0], tokens(&[
+            (5, "let"), (1, "s"), (1, ":"), (6, "String"), (7, "="), (8, "\"a b\""), (7, "*"), (10, "2"), (1, ";"), (9, "// hi"),
+        ]));
+
+        let python = buffer("tokens.py", "x = 'it\"s' # done");
+        assert_eq!(highlighted(&mut bundled_syntax("python"), &python)[0], tokens(&[
+            (1, "x"), (7, "="), (8, "'it\"s'"), (9, "# done"),
+        ]));
+    }
+
+    #[test]
+    fn rust_tokens_are_colored() {
+        let text = "let x = 0xffu8 + 1_000u32 + 1.5e-3;\n\
+                    fn f<'a>(c: char) -> char { '\\n'; 'x' }\n\
+                    #[derive(Debug)]\n\
+                    /// docs\n\
+                    println!(\"hi\"); // note\n\
+                    if x!=y { vec![x] }";
+        let buffer = buffer("classes.rs", text);
+        let rows = highlighted(&mut bundled_syntax("rust"), &buffer);
+
+        assert_eq!(rows[0], tokens(&[
+            (5, "let"), (1, "x"), (7, "="), (10, "0xffu8"), (7, "+"), (10, "1_000u32"), (7, "+"), (10, "1.5e-3"), (1, ";"),
+        ]));
+        assert_eq!(rows[1], tokens(&[
+            (5, "fn"), (1, "f"), (7, "<"), (12, "'a"), (7, ">"), (1, "("), (1, "c"), (1, ":"), (6, "char"), (1, ")"),
+            (7, "-"), (7, ">"), (6, "char"), (1, "{"), (11, "'\\n'"), (1, ";"), (11, "'x'"), (1, "}"),
+        ]));
+        assert_eq!(rows[2], tokens(&[(14, "#[derive(Debug)]")]));
+        assert_eq!(rows[3], tokens(&[(15, "/// docs")]));
+        assert_eq!(rows[4], tokens(&[(13, "println!"), (1, "("), (8, "\"hi\""), (1, ")"), (1, ";"), (9, "// note")]));
+        assert_eq!(rows[5], tokens(&[
+            (5, "if"), (1, "x"), (1, "!"), (7, "="), (1, "y"), (1, "{"), (13, "vec!"), (1, "["), (1, "x"), (1, "]"), (1, "}"),
+        ]));
+    }
+
+    #[test]
+    fn state_carries_over_lines() {
+        let text = "let s = r#\"a\nb \" c \"#;\n/* one /* two */\nstill */ x\n\"multi\nline\" y";
+        let buffer = buffer("state.rs", text);
+
+        // asking for a later row first works out the rows above it
+        let mut syntax = bundled_syntax("rust");
+        assert_eq!(syntax.highlight_line(&buffer, 3).last(), Some(&(1, String::from("x"))));
+
+        let rows = highlighted(&mut syntax, &buffer);
+        assert_eq!(rows[0], tokens(&[(5, "let"), (1, "s"), (7, "="), (8, "r#\"a")]));
+        assert_eq!(rows[1], tokens(&[(8, "b \" c \"#"), (1, ";")]));
+        assert_eq!(rows[2], tokens(&[(9, "/* one /* two */")]));
+        assert_eq!(rows[3], tokens(&[(9, "still */"), (1, "x")]));
+        assert_eq!(rows[4], tokens(&[(8, "\"multi")]));
+        assert_eq!(rows[5], tokens(&[(8, "line\""), (1, "y")]));
+    }
+
+    #[test]
+    fn edits_above_the_cache_invalidate_it() {
+        let mut buffer = buffer("invalidate.rs", "let a = 1;\nlet b = 2;\nlet c = 3;");
+        let mut syntax = bundled_syntax("rust");
+        assert_eq!(highlighted(&mut syntax, &buffer)[2][0], (5, String::from("let")));
+
+        buffer.insert_text(0, "/*");
+        syntax.update(&mut buffer);
+        assert_eq!(highlighted(&mut syntax, &buffer)[2], tokens(&[(9, "let c = 3;")]));
+
+        buffer.remove_text(0..2);
+        syntax.update(&mut buffer);
+        assert_eq!(highlighted(&mut syntax, &buffer)[2][0], (5, String::from("let")));
+    }
+
+    #[test]
+    fn bad_definitions_are_reported() {
+        assert_eq!(Syntax::parse("colour = \"red\"").unwrap_err(), "unknown field `colour`");
+        assert_eq!(Syntax::parse("keywords = \"fn\"").unwrap_err(), "`keywords` must be a list of strings");
+        assert_eq!(Syntax::parse("comments = [\"\"]").unwrap_err(), "string and comment markers can't be empty");
+    }
+}
@@ -0,0 +1,229 @@
+use ropey::Rope;
+use tree_sitter::{InputEdit, Language, Parser, Point, Query, QueryCursor, Tree};
+
+use crate::buffer::{Buffer, TextChange};
+
+pub struct TreeSitter {
+    name: String,
+    parser: Parser,
+    query: Query,
+    colors: Vec<i16>,
+    tree: Option<Tree>,
+}
+
+// grammars compiled into zin, keyed by the name of the syntax definition they take over from
+fn grammar(name: &str) -> Option<(Language, &'static str)> {
+    return match name {
+        "rust" => Some((tree_sitter_rust::LANGUAGE.into(), tree_sitter_rust::HIGHLIGHTS_QUERY)),
+        "python" => Some((tree_sitter_python::LANGUAGE.into(), tree_sitter_python::HIGHLIGHTS_QUERY)),
+        "c" => Some((tree_sitter_c::LANGUAGE.into(), tree_sitter_c::HIGHLIGHT_QUERY)),
+        _ => None,
+    };
+}
+
+// maps a capture such as @function.macro onto the colour pairs from Window::init_colors
+fn color_of_capture(name: &str) -> i16 {
+    match name {
+        "function.macro" => return 13,
+        "comment.documentation" => return 15,
+        "constant.character" | "string.special.char" => return 11,
+        _ => {},
+    }
+
+    return match name.split('.').next().unwrap_or(name) {
+        "keyword" | "conditional" | "repeat" | "include" | "storageclass" => 5,
+        "type" | "constructor" => 6,
+        "function" | "operator" | "constant" | "boolean" => 7,
+        "string" | "escape" => 8,
+        "comment" => 9,
+        "number" | "float" => 10,
+        "char" | "character" => 11,
+        "label" | "lifetime" => 12,
+        "attribute" => 14,
+        _ => 1,
+    };
+}
+
+fn point((row, column): (usize, usize)) -> Point {
+    return Point { row, column };
+}
+
+fn chunk_at(text: &Rope, byte: usize) -> &[u8] {
+    if byte >= text.len_bytes() {
+        return &[];
+    }
+    let (chunk, chunk_byte, _, _) = text.chunk_at_byte(byte);
+    return &chunk.as_bytes()[byte - chunk_byte..];
+}
+
+impl TreeSitter {
+    pub fn new(name: &str) -> Option<TreeSitter> {
+        let (language, highlights) = grammar(name)?;
+        let mut parser = Parser::new();
+        parser.set_language(&language).ok()?;
+        let query = Query::new(&language, highlights).ok()?;
+        let colors = query.capture_names().iter().map(|name| color_of_capture(name)).collect();
+
+        return Some(TreeSitter {
+            name: name.to_string(),
+            parser,
+            query,
+            colors,
+            tree: None,
+        });
+    }
+
+    // edits the old tree in place and reparses, which only revisits the changed nodes
+    pub fn update(&mut self, buffer: &Buffer, changes: &[TextChange]) {
+        if let Some(tree) = &mut self.tree {
+            for change in changes {
+                tree.edit(&InputEdit {
+                    start_byte: change.start_byte,
+                    old_end_byte: change.old_end_byte,
+                    new_end_byte: change.new_end_byte,
+                    start_position: point(change.start),
+                    old_end_position: point(change.old_end),
+                    new_end_position: point(change.new_end),
+                });
+            }
+        }
+
+        if self.tree.is_none() || !changes.is_empty() {
+            let text = buffer.rope();
+            self.tree = self.parser.parse_with(&mut |byte, _| chunk_at(text, byte), self.tree.as_ref());
+        }
+    }
+
+    pub fn highlight_line(&self, buffer: &Buffer, row: usize) -> Vec<(i16, String)> {
+        let line = buffer.line(row);
+        let tree = match &self.tree {
+            Some(tree) => tree,
+            None => return vec![(1, line)],
+        };
+
+        let text = buffer.rope();
+        let line_start = text.line_to_byte(row);
+        let line_end = line_start + line.len();
+
+        // larger nodes are painted first so the captures nested inside them win,
+        // and for the same node the earliest pattern in the query is painted last
+        let mut captures = Vec::new();
+        let mut cursor = QueryCursor::new();
+        cursor.set_byte_range(line_start..line_end);
+        let provider = |node: tree_sitter::Node| text.byte_slice(node.byte_range()).chunks().map(str::as_bytes);
+        for matched in cursor.matches(&self.query, tree.root_node(), provider) {
+            for capture in matched.captures {
+                let range = capture.node.byte_range();
+                captures.push((range.end - range.start, matched.pattern_index, range, self.colors[capture.index as usize]));
+            }
+        }
+        captures.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.cmp(&a.1)));
+
+        let mut colors = vec![1; line.len()];
+        for (_, _, range, color) in captures {
+            let start = range.start.max(line_start) - line_start;
+            let end = range.end.min(line_end).saturating_sub(line_start);
+            if start < end {
+                colors[start..end].fill(color);
+            }
+        }
+
+        let mut highlighted: Vec<(i16, String)> = Vec::new();
+        for (byte, character) in line.char_indices() {
+            match highlighted.last_mut() {
+                Some((color, token)) if *color == colors[byte] => token.push(character),
+                _ => highlighted.push((colors[byte], character.to_string())),
+            }
+        }
+        return highlighted;
+    }
+}
+
+impl std::fmt::Debug for TreeSitter {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        return f.debug_struct("TreeSitter").field("name", &self.name).finish();
+    }
+}
+
+impl Clone for TreeSitter {
+    // parsers and queries can't be cloned, so the copy is built again from the grammar.
+    // new only fails for a name without a grammar or a language or query the parser rejects,
+    // all of them are compiled in and were accepted when self was built from the same name
+    fn clone(&self) -> TreeSitter {
+        return TreeSitter::new(&self.name).expect("compiled grammar was accepted before");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::process;
+    use crate::Window;
+    use super::*;
+
+    // every test gets its own file so they can run in parallel
+    fn rust_buffer(name: &str, contents: &str) -> (Buffer, TreeSitter) {
+        let dir = env::temp_dir().join(format!("zin-test-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        let buffer = Buffer::new(&path.to_string_lossy()).unwrap();
+        let mut tree_sitter = TreeSitter::new("rust").unwrap();
+        tree_sitter.update(&buffer, &[]);
+        return (buffer, tree_sitter);
+    }
+
+    fn colors(tree_sitter: &TreeSitter, buffer: &Buffer, row: usize) -> Vec<(i16, String)> {
+        let tokens = tree_sitter.highlight_line(buffer, row);
+        return tokens.into_iter().filter(|(_, token)| !token.trim().is_empty()).collect();
+    }
+
+    #[test]
+    fn captures_map_onto_color_pairs() {
+        assert_eq!(color_of_capture("keyword"), 5);
+        assert_eq!(color_of_capture("keyword.function"), 5);
+        assert_eq!(color_of_capture("type.builtin"), 6);
+        assert_eq!(color_of_capture("function.macro"), 13);
+        assert_eq!(color_of_capture("function"), 7);
+        assert_eq!(color_of_capture("comment.documentation"), 15);
+        assert_eq!(color_of_capture("constant.character"), 11);
+        assert_eq!(color_of_capture("number"), 10);
+        assert_eq!(color_of_capture("label"), 12);
+        assert_eq!(color_of_capture("attribute"), 14);
+        assert_eq!(color_of_capture("string"), 8);
+        assert_eq!(color_of_capture("variable.parameter"), 1);
+        assert_eq!(color_of_capture(""), 1);
+        assert!(TreeSitter::new("yaml").is_none());
+    }
+
+    #[test]
+    fn lines_are_highlighted_from_the_tree() {
+        let (buffer, tree_sitter) = rust_buffer("tree.rs", "fn main() {\n    let s: &str = \"hi\"; // note\n}");
+        let tokens = colors(&tree_sitter, &buffer, 1);
+        assert_eq!(tokens.first(), Some(&(5, String::from("let"))));
+        assert!(tokens.contains(&(6, String::from("str"))));
+        assert!(tokens.contains(&(8, String::from("\"hi\""))));
+        assert_eq!(tokens.last(), Some(&(9, String::from("// note"))));
+
+        // a clone parses again before it has a tree to highlight from
+        assert_eq!(tree_sitter.clone().highlight_line(&buffer, 1), vec![(1, buffer.line(1))]);
+    }
+
+    #[test]
+    fn edits_are_parsed_again() {
+        let (mut buffer, mut tree_sitter) = rust_buffer("edit.rs", "fn main() {\n    let x = 1;\n}");
+        let mut window = Window { cursor_col: 0, cursor_row: 1, win_row: 0, win_width: 80, win_height: 24, win_top: 0, win_left: 0, buffer: 0, desired_col: None };
+        assert_eq!(colors(&tree_sitter, &buffer, 1)[0], (5, String::from("let")));
+
+        buffer.insert_text(buffer.char_idx(1, 0), "// ");
+        let changes = buffer.take_changes();
+        tree_sitter.update(&buffer, &changes);
+        assert_eq!(colors(&tree_sitter, &buffer, 1), vec![(9, String::from("//     let x = 1;"))]);
+
+        assert!(buffer.undo(&mut window));
+        let changes = buffer.take_changes();
+        tree_sitter.update(&buffer, &changes);
+        assert_eq!(colors(&tree_sitter, &buffer, 1)[0], (5, String::from("let")));
+    }
+}
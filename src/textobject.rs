@@ -0,0 +1,313 @@
+use ropey::Rope;
+
+use crate::buffer::Buffer;
+use crate::motion::class;
+use crate::operator::Region;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Object {
+    Word(bool),
+    Quote(char),
+    Pair(char, char),
+    Paragraph,
+    Tag,
+}
+
+// iw, a(, it and the rest, `around` for the a forms that take the surroundings too
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextObject {
+    pub object: Object,
+    pub around: bool,
+}
+
+impl TextObject {
+    // `prefix` is the i or a typed before the key naming the object
+    pub fn from_keys(prefix: i32, char_code: i32) -> Option<TextObject> {
+        let around = match prefix {
+            105 => false,
+            97 => true,
+            _ => return None,
+        };
+        let object = match char::from_u32(char_code as u32)? {
+            'w' => Object::Word(false),
+            'W' => Object::Word(true),
+            quote @ ('"' | '\'' | '`') => Object::Quote(quote),
+            '(' | ')' | 'b' => Object::Pair('(', ')'),
+            '[' | ']' => Object::Pair('[', ']'),
+            '{' | '}' | 'B' => Object::Pair('{', '}'),
+            '<' | '>' => Object::Pair('<', '>'),
+            'p' => Object::Paragraph,
+            't' => Object::Tag,
+            _ => return None,
+        };
+        return Some(TextObject { object, around });
+    }
+
+    // the text the object covers around `pos`, None when there is no such object there
+    pub fn region(self, buffer: &Buffer, pos: (usize, usize), count: Option<usize>) -> Option<Region> {
+        let times = count.unwrap_or(1).max(1);
+        return match self.object {
+            Object::Word(big) => word(buffer, pos, times, big, self.around),
+            Object::Quote(quote) => quoted(buffer, pos, quote, self.around),
+            Object::Pair(open, close) => pair(buffer, pos, times, open, close, self.around),
+            Object::Paragraph => Some(paragraph(buffer, pos.0, times, self.around)),
+            Object::Tag => tag(buffer, pos, times, self.around),
+        };
+    }
+}
+
+// the end of the run of chars of one class that `col` is in
+fn run_end(line: &[char], col: usize, big: bool) -> usize {
+    let kind = class(line.get(col).copied(), big);
+    let mut end = col;
+    while end < line.len() && class(Some(line[end]), big) == kind {
+        end += 1;
+    }
+    return end;
+}
+
+fn run_start(line: &[char], col: usize, big: bool) -> usize {
+    let kind = class(line.get(col).copied(), big);
+    let mut start = col;
+    while start > 0 && class(Some(line[start - 1]), big) == kind {
+        start -= 1;
+    }
+    return start;
+}
+
+// blanks count as a word of their own for iw, aw takes a word with the blanks after it,
+// or the ones before it when it ends the line
+fn word(buffer: &Buffer, (row, col): (usize, usize), times: usize, big: bool, around: bool) -> Option<Region> {
+    let line = buffer.line(row).chars().collect::<Vec<char>>();
+    if line.is_empty() {
+        return None;
+    }
+
+    let col = col.min(line.len() - 1);
+    let mut start = run_start(&line, col, big);
+    let mut end = col;
+    for index in 0..times {
+        if end >= line.len() {
+            break;
+        }
+        if !around {
+            end = run_end(&line, end, big);
+            continue;
+        }
+
+        let on_blank = class(Some(line[end]), big) == 0;
+        end = run_end(&line, end, big);
+        if on_blank {
+            // aw from a blank takes the blanks and the word after them
+            if end < line.len() {
+                end = run_end(&line, end, big);
+            }
+        } else if end < line.len() && class(Some(line[end]), big) == 0 {
+            end = run_end(&line, end, big);
+        } else if index == 0 {
+            while start > 0 && class(Some(line[start - 1]), big) == 0 {
+                start -= 1;
+            }
+        }
+    }
+    return Some(Region { start: (row, start), end: (row, end), linewise: false });
+}
+
+// quotes pair up from the start of the line, so the cursor can sit on either one or before the first
+fn quoted(buffer: &Buffer, (row, col): (usize, usize), quote: char, around: bool) -> Option<Region> {
+    let line = buffer.line(row).chars().collect::<Vec<char>>();
+    let mut quotes = Vec::new();
+    let mut index = 0;
+    while index < line.len() {
+        if line[index] == '\\' {
+            index += 2;
+            continue;
+        }
+        if line[index] == quote {
+            quotes.push(index);
+        }
+        index += 1;
+    }
+
+    let pairs = quotes.chunks_exact(2).map(|pair| (pair[0], pair[1])).collect::<Vec<(usize, usize)>>();
+    let (open, close) = match pairs.iter().find(|(open, close)| *open <= col && col <= *close) {
+        Some(pair) => *pair,
+        None => *pairs.iter().find(|(open, _)| *open > col)?,
+    };
+
+    if !around {
+        return Some(Region { start: (row, open + 1), end: (row, close), linewise: false });
+    }
+    let mut start = open;
+    let mut end = close + 1;
+    if end < line.len() && line[end].is_whitespace() {
+        while end < line.len() && line[end].is_whitespace() {
+            end += 1;
+        }
+    } else {
+        while start > 0 && line[start - 1].is_whitespace() {
+            start -= 1;
+        }
+    }
+    return Some(Region { start: (row, start), end: (row, end), linewise: false });
+}
+
+fn text_pos(buffer: &Buffer, idx: usize) -> (usize, usize) {
+    let row = buffer.rope().char_to_line(idx);
+    return (row, idx - buffer.rope().line_to_char(row));
+}
+
+// the `times`th bracket pair around the cursor, a cursor on a bracket is inside its pair
+fn pair(buffer: &Buffer, pos: (usize, usize), times: usize, open: char, close: char, around: bool) -> Option<Region> {
+    let text = buffer.rope();
+    let cursor = buffer.char_idx(pos.0 as i32, pos.1 as i32).min(text.len_chars().checked_sub(1)?);
+
+    // on a closing bracket the search starts inside the pair it belongs to
+    let mut start = cursor;
+    if text.char(cursor) != open {
+        start = find_open(text, cursor, open, close)?;
+    }
+    for _ in 1..times {
+        start = find_open(text, start, open, close)?;
+    }
+
+    let mut end = None;
+    let mut depth = 0;
+    for (offset, character) in text.chars_at(start + 1).enumerate() {
+        if character == open {
+            depth += 1;
+        } else if character == close && depth == 0 {
+            end = Some(start + 1 + offset);
+            break;
+        } else if character == close {
+            depth -= 1;
+        }
+    }
+    let end = end?;
+
+    if around {
+        return Some(Region { start: text_pos(buffer, start), end: text_pos(buffer, end + 1), linewise: false });
+    }
+
+    // a pair whose brackets end and start their lines holds just the lines in between
+    let (open_pos, close_pos) = (text_pos(buffer, start), text_pos(buffer, end));
+    let open_last = open_pos.1 + 1 == buffer.line_len(open_pos.0);
+    let close_first = buffer.line(close_pos.0).chars().take(close_pos.1).all(char::is_whitespace);
+    if open_last && close_first && close_pos.0 > open_pos.0 + 1 {
+        return Some(Region::lines(open_pos.0 + 1, close_pos.0 - 1));
+    }
+    return Some(Region { start: text_pos(buffer, start + 1), end: close_pos, linewise: false });
+}
+
+// the unmatched opening bracket before `idx`
+fn find_open(text: &Rope, idx: usize, open: char, close: char) -> Option<usize> {
+    let mut depth = 0;
+    for (offset, character) in text.chars_at(idx).reversed().enumerate() {
+        if character == close {
+            depth += 1;
+        } else if character == open {
+            if depth == 0 {
+                return Some(idx - 1 - offset);
+            }
+            depth -= 1;
+        }
+    }
+    return None;
+}
+
+// a paragraph is a run of lines that are all blank or all not, ap adds the blank lines after it,
+// or before it when there are none after
+fn paragraph(buffer: &Buffer, row: usize, times: usize, around: bool) -> Region {
+    let last_row = buffer.len_lines() - 1;
+    let blank = |row: usize| buffer.line(row).trim().is_empty();
+    let block_end = |row: usize| {
+        let mut end = row;
+        while end < last_row && blank(end + 1) == blank(row) {
+            end += 1;
+        }
+        return end;
+    };
+
+    let mut start = row;
+    while start > 0 && blank(start - 1) == blank(row) {
+        start -= 1;
+    }
+
+    let mut end = block_end(row);
+    let steps = if around { times * 2 } else { times };
+    for _ in 1..steps {
+        if end == last_row {
+            break;
+        }
+        end = block_end(end + 1);
+    }
+
+    if around && !blank(row) && blank(end) == blank(row) {
+        // nothing blank followed the paragraph, so take the blank lines before it
+        while start > 0 && blank(start - 1) {
+            start -= 1;
+        }
+    }
+    return Region::lines(start, end);
+}
+
+// the `times`th tag pair around the cursor, it is what is between the tags and at includes them
+fn tag(buffer: &Buffer, pos: (usize, usize), times: usize, around: bool) -> Option<Region> {
+    let cursor = buffer.char_idx(pos.0 as i32, pos.1 as i32);
+
+    // pairs close from the innermost out, so the ones around the cursor are met in the order they are counted
+    let mut open: Vec<(String, usize, usize)> = Vec::new();
+    let mut found = 0;
+    let mut chars = buffer.rope().chars().enumerate().peekable();
+    let named = |character: char| character.is_alphanumeric() || "-_:.".contains(character);
+    while let Some((index, character)) = chars.next() {
+        if index > cursor && open.iter().all(|(_, open_start, _)| *open_start > cursor) {
+            // no tag left open before the cursor, so nothing further on can be around it
+            return None;
+        }
+        if character != '<' {
+            continue;
+        }
+
+        // `a < b` is no tag, a tag has its name straight after the `<` and ends on its line,
+        // whatever stops one is left to be read again
+        let mut inner = String::new();
+        let mut end = None;
+        while let Some(&(offset, character)) = chars.peek() {
+            if character == '>' {
+                chars.next();
+                end = Some(offset + 1);
+                break;
+            }
+            let naming = inner.is_empty() || inner == "/";
+            if character == '\n' || character == '<' || (naming && !named(character) && !(inner.is_empty() && character == '/')) {
+                break;
+            }
+            inner.push(character);
+            chars.next();
+        }
+        let end = match end {
+            Some(end) => end,
+            None => continue,
+        };
+        let closing = inner.starts_with('/');
+        let name = inner.trim_start_matches('/').chars().take_while(|character| named(*character)).collect::<String>();
+
+        if name.is_empty() || inner.ends_with('/') {
+            // comments, doctypes and self closing tags hold nothing
+        } else if !closing {
+            open.push((name, index, end));
+        } else if let Some(matched) = open.iter().rposition(|(open_name, _, _)| *open_name == name) {
+            let (_, open_start, open_end) = open[matched];
+            open.truncate(matched);
+            if open_start <= cursor && cursor < end {
+                found += 1;
+                if found == times {
+                    let (start, end) = if around { (open_start, end) } else { (open_end, index) };
+                    return Some(Region { start: text_pos(buffer, start), end: text_pos(buffer, end), linewise: false });
+                }
+            }
+        }
+    }
+    return None;
+}
@@ -0,0 +1,233 @@
+use regex::Regex;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::backend::Backend;
+use crate::buffer::{advance, Buffer};
+use crate::editor::{Modes, Shape, Visual};
+use crate::syntax::Syntax;
+
+#[derive(Debug)]
+pub struct Window {
+    pub cursor_col: i32,
+    pub cursor_row: i32,
+    pub win_row: i32,
+    pub win_width: i32,
+    pub win_height: i32,
+    pub win_top: i32,
+    pub win_left: i32,
+    // index into the buffer list
+    pub buffer: usize,
+    // the display column j and k keep to over lines too short for it, set by the first of them
+    // and dropped by anything else that moves the cursor
+    pub desired_col: Option<usize>,
+}
+
+impl Window {
+    // draws the window at its place in the layout, mode and selection are only given to the focused one
+    pub fn display(
+        &self,
+        buffer: &Buffer,
+        backend: &mut dyn Backend,
+        mode: Option<&Modes>,
+        selection: Option<&Visual>,
+        search: Option<&Regex>,
+        syntax: &mut Syntax
+    ) {
+        // a terminal shrunk below what the layout needs squeezes some windows out of existence
+        if self.win_height < 1 || self.win_width < 1 {
+            return;
+        }
+        let width = self.win_width as usize;
+
+        // render text
+        let mut index = self.win_row;
+        while index < self.win_row + self.win_height - 1 {
+            let row = self.win_top + index - self.win_row;
+            let mut highlighted_line = if buffer.len_lines() <= index as usize {
+                vec![(1, String::from("~"))]
+            } else {
+                syntax.highlight_line(buffer, index as usize)
+            };
+
+            if let Some(pattern) = search.filter(|_| (index as usize) < buffer.len_lines()) {
+                let found = buffer.find(pattern, index as usize);
+                if !found.is_empty() {
+                    highlighted_line = recolored(highlighted_line, |col| found.iter().any(|range| range.contains(&(col as usize))).then_some(17));
+                }
+            }
+
+            let selection = selection.filter(|visual| {
+                let (start, end) = visual.ordered();
+                return (index as usize) < buffer.len_lines() && start.0 <= index && index <= end.0;
+            });
+            if let Some(visual) = selection {
+                highlighted_line = recolored(highlighted_line, |col| visual.contains(index, col).then_some(16));
+            }
+
+            let mut counter = 0;
+            for token in highlighted_line {
+                let (text, end) = clipped(&token.1, counter, width);
+                backend.draw(row, self.win_left + counter as i32, &expanded(text, counter), token.0);
+                counter = end;
+            }
+
+            // a selection that takes the line break shows it as one more cell
+            if let Some(visual) = selection {
+                let line_len = buffer.line_len(index as usize) as i32;
+                if visual.shape != Shape::Block && visual.contains(index, line_len) && counter < width {
+                    backend.draw(row, self.win_left + counter as i32, " ", 16);
+                    counter += 1;
+                }
+            }
+            backend.draw(row, self.win_left + counter as i32, &" ".repeat(width - counter), 1);
+            index += 1;
+        }
+
+        // render bar
+        let row = self.win_top + self.win_height - 1;
+        let mut counter = 0;
+        if let Some(mode) = mode {
+            let mode = &match selection {
+                Some(visual) if *mode == Modes::Visual => visual.label().to_string(),
+                _ => format!(" {:?} ", mode).to_uppercase(),
+            };
+            let attr: i16 = if mode == " NORMAL " { 2 } else { 4 };
+            let (mode, mode_width) = clipped(mode, 0, width);
+            backend.draw(row, self.win_left, mode, attr);
+            counter += mode_width;
+        }
+
+        let name_col = (width / 2).saturating_sub(buffer.buf_name.width() / 2).max(counter);
+        let (name, name_end) = clipped(&buffer.buf_name, name_col, width);
+        let name_width = name_end - name_col;
+        let bar = format!("{}{}{}", " ".repeat(name_col - counter), name, " ".repeat(width - name_col - name_width));
+        backend.draw(row, self.win_left + counter as i32, &bar, 3);
+    }
+
+    pub fn left(&mut self, buffer: &Buffer) {
+        self.cursor_col = buffer.prev_grapheme(self.cursor_row as usize, self.cursor_col as usize) as i32;
+        self.desired_col = None;
+    }
+
+    pub fn right(&mut self, buffer: &Buffer) {
+        self.cursor_col = buffer.next_grapheme(self.cursor_row as usize, self.cursor_col as usize) as i32;
+        self.desired_col = None;
+    }
+
+    pub fn down(&mut self, buffer: &Buffer) {
+        if self.cursor_row + 1 < buffer.len_lines() as i32 {
+            self.vertical(buffer, self.cursor_row + 1, true);
+        }
+    }
+
+    pub fn up(&mut self, buffer: &Buffer) {
+        if self.cursor_row != 0 {
+            self.vertical(buffer, self.cursor_row - 1, true);
+        }
+    }
+
+    // goes to `row` at the desired column, or as near to it as the line allows, `past_end` lets
+    // the cursor stop after the last char like it can in insert mode
+    pub fn vertical(&mut self, buffer: &Buffer, row: i32, past_end: bool) {
+        let desired = self.desired_col.unwrap_or_else(|| buffer.display_col(self.cursor_row as usize, self.cursor_col as usize));
+        let row = row.clamp(0, buffer.len_lines() as i32 - 1);
+        let mut col = buffer.col_at_width(row as usize, desired);
+        if !past_end && col == buffer.line_len(row as usize) {
+            col = buffer.prev_grapheme(row as usize, col);
+        }
+        self.restore_cursor(buffer, (row, col as i32));
+        self.desired_col = Some(desired);
+    }
+
+    pub fn clamp_col(&mut self, buffer: &Buffer) {
+        if buffer.line_len(self.cursor_row as usize) < self.cursor_col as usize {
+            self.cursor_col = buffer.line_len(self.cursor_row as usize) as i32;
+        }
+        self.cursor_col = buffer.grapheme_start(self.cursor_row as usize, self.cursor_col as usize) as i32;
+    }
+
+    pub fn scroll_to_cursor(&mut self) {
+        let rows = (self.win_height - 1).max(1);
+        if self.cursor_row < self.win_row {
+            self.win_row = self.cursor_row;
+        } else if self.cursor_row >= self.win_row + rows {
+            self.win_row = self.cursor_row - rows + 1;
+        }
+    }
+
+    pub fn restore_cursor(&mut self, buffer: &Buffer, cursor: (i32, i32)) {
+        self.cursor_row = cursor.0;
+        self.cursor_col = cursor.1;
+        self.desired_col = None;
+        self.fit(buffer);
+    }
+
+    // keeps the cursor on the text after an edit, which may have been made in another window
+    pub fn fit(&mut self, buffer: &Buffer) {
+        self.cursor_row = self.cursor_row.min(buffer.len_lines() as i32 - 1);
+        self.clamp_col(buffer);
+        self.scroll_to_cursor();
+    }
+
+    pub fn check_move(&mut self, buffer: &Buffer, char_code: i32) -> bool {
+        if char_code == ncurses::KEY_LEFT {
+            self.left(buffer);
+        } else if char_code == ncurses::KEY_RIGHT {
+            self.right(buffer);
+        } else if char_code == ncurses::KEY_DOWN {
+            self.down(buffer);
+        } else if char_code == ncurses::KEY_UP {
+            self.up(buffer);
+        } else {
+            return false;
+        }
+        return true;
+    }
+}
+
+// the longest prefix of `text` drawn from column `from` that ends by column `width`, along with the column it ends at
+pub fn clipped(text: &str, from: usize, width: usize) -> (&str, usize) {
+    let mut used = from;
+    for (byte, grapheme) in text.grapheme_indices(true) {
+        let next = advance(used, grapheme);
+        if next > width {
+            return (&text[..byte], used);
+        }
+        used = next;
+    }
+    return (text, used);
+}
+
+// `text` drawn from column `from` with its tabs as spaces, the terminal would line them up with its own tab stops
+fn expanded(text: &str, from: usize) -> String {
+    let mut col = from;
+    let mut spaced = String::new();
+    for grapheme in text.graphemes(true) {
+        let next = advance(col, grapheme);
+        if grapheme == "\t" {
+            spaced.push_str(&" ".repeat(next - col));
+        } else {
+            spaced.push_str(grapheme);
+        }
+        col = next;
+    }
+    return spaced;
+}
+
+// splits the tokens of a line where `pair_at` changes, columns it gives a pair for are drawn in that pair
+fn recolored(tokens: Vec<(i16, String)>, pair_at: impl Fn(i32) -> Option<i16>) -> Vec<(i16, String)> {
+    let mut split: Vec<(i16, String)> = Vec::new();
+    let mut col = 0;
+    for (pair, text) in tokens {
+        for character in text.chars() {
+            let pair = pair_at(col).unwrap_or(pair);
+            match split.last_mut() {
+                Some(last) if last.0 == pair => last.1.push(character),
+                _ => split.push((pair, character.to_string())),
+            }
+            col += 1;
+        }
+    }
+    return split;
+}
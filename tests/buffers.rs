@@ -0,0 +1,30 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use zin::backend::headless::Headless;
+use zin::config::Config;
+use zin::syntax::Registry;
+use zin::Editor;
+
+// an editor opened on one file per name, each holding its own name, along with their paths
+fn editor(names: &[&str]) -> (Editor, Vec<String>) {
+    let paths = names.iter().map(|name| common::temp_file(name, &format!("{}\nsecond\n", name))).collect::<Vec<String>>();
+    return (Editor::new(Config::init(), Registry::load().unwrap(), &paths).unwrap(), paths);
+}
+
+fn run(editor: &mut Editor, screen: &mut Headless, keys: &str) -> String {
+    screen.feed_str(keys);
+    editor.run(screen);
+    return editor.command_line().to_string();
+}
+
+#[test]
+fn ls_lists_a_buffer_per_line() {
+    let (mut editor, paths) = editor(&["ls-a.txt", "ls-b.txt"]);
+    let mut screen = Headless::new(6, 60);
+    let listed = run(&mut editor, &mut screen, "ix\x1b:bn\n:ls\n");
+    assert_eq!(listed, format!("1  \"{}\" +\n2% \"{}\"", paths[0], paths[1]));
+    assert_eq!(screen.line(4).trim_end(), format!("1  \"{}\" +", paths[0]));
+    assert_eq!(screen.line(5).trim_end(), format!("2% \"{}\"", paths[1]));
+}
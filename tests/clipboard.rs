@@ -0,0 +1,98 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use zin::backend::headless::Headless;
+use zin::config::Config;
+use zin::syntax::Registry;
+use zin::Editor;
+
+use common::{dir, text};
+
+const TEXT: &str = "one two\nthree\n";
+
+// stand-ins for xclip and friends, all written before any test runs one, since running a script
+// while another thread still has it open for writing fails with ETXTBSY
+fn fake_command(name: &str) -> String {
+    static SCRIPTS: OnceLock<PathBuf> = OnceLock::new();
+    let scripts = SCRIPTS.get_or_init(|| {
+        let scripts = dir("scripts");
+        let bodies = [
+            ("copy", "cat > \"$1\""),
+            ("paste", "printf 'from outside'"),
+            ("lines", "printf 'a\\nb\\n'"),
+            ("fail", "exit 3"),
+        ];
+        for (name, body) in bodies {
+            let path = scripts.join(name);
+            fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        return scripts;
+    });
+    return scripts.join(name).to_string_lossy().to_string();
+}
+
+fn editor(dir: &Path, settings: &str) -> Editor {
+    let path = dir.join("text.txt");
+    fs::write(&path, TEXT).unwrap();
+    let mut configuration = Config::init();
+    configuration.apply(settings).unwrap();
+    return Editor::new(configuration, Registry::load().unwrap(), &[path.to_string_lossy().to_string()]).unwrap();
+}
+
+#[test]
+fn yanks_go_through_the_copy_command_and_osc52() {
+    let dir = dir("copy");
+    let copy = format!("{} {}", fake_command("copy"), dir.join("copied").display());
+    let mut editor = editor(&dir, &format!("[clipboard]\ncopy = \"{}\"\n", copy));
+    let mut screen = Headless::new(10, 40);
+    screen.feed_str("\"+yy");
+    editor.run(&mut screen);
+
+    assert_eq!(fs::read_to_string(dir.join("copied")).unwrap(), "one two\n");
+    assert_eq!(screen.clipboard(), Some("one two"));
+
+    screen.feed_str("\"*yw");
+    editor.run(&mut screen);
+    assert_eq!(fs::read_to_string(dir.join("copied")).unwrap(), "one ");
+}
+
+#[test]
+fn pastes_come_from_the_paste_command() {
+    let dir = dir("paste");
+    let paste = fake_command("paste");
+    let mut editor = editor(&dir, &format!("[clipboard]\npaste = \"{}\"\nosc52 = false\n", paste));
+    let mut screen = Headless::new(10, 40);
+    screen.feed_str("\"+P");
+    editor.run(&mut screen);
+
+    assert_eq!(text(&editor), "from outsideone two\nthree");
+    assert_eq!(screen.clipboard(), None);
+
+    let lines = fake_command("lines");
+    let mut editor = self::editor(&dir, &format!("[clipboard]\npaste = \"{}\"\n", lines));
+    screen.feed_str("\"*p");
+    editor.run(&mut screen);
+    assert_eq!(text(&editor), "one two\na\nb\nthree");
+}
+
+#[test]
+fn a_failing_command_is_reported() {
+    let dir = dir("failing");
+    let fail = fake_command("fail");
+    let mut editor = editor(&dir, &format!("[clipboard]\ncopy = \"{}\"\npaste = \"{}\"\n", fail, fail));
+    let mut screen = Headless::new(10, 40);
+    screen.feed_str("\"+yy");
+    editor.run(&mut screen);
+    assert!(editor.command_line().contains("exited with"));
+
+    screen.feed_str("\"+p");
+    editor.run(&mut screen);
+    assert_eq!(text(&editor), "one two\nthree");
+}
@@ -0,0 +1,58 @@
+// every test file uses a different handful of these
+#![allow(dead_code)]
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+use zin::backend::headless::Headless;
+use zin::config::Config;
+use zin::syntax::Registry;
+use zin::Editor;
+
+// a little rust for the motion and operator tests to move over
+pub const CODE: &str = "fn main() {\n    let words = foo.bar(1, 2);\n\n    call(x[0]);\n}\n";
+
+// a directory of its own for this test run, tests running in parallel use different names in it
+pub fn dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("zin-test-{}", process::id())).join(name);
+    fs::create_dir_all(&dir).unwrap();
+    return dir;
+}
+
+// every test gets its own file so they can run in parallel
+pub fn temp_file(name: &str, contents: &str) -> String {
+    let path = dir("").join(name);
+    fs::write(&path, contents).unwrap();
+    return path.to_string_lossy().to_string();
+}
+
+pub fn editor(name: &str, contents: &str) -> Editor {
+    return Editor::new(Config::init(), Registry::load().unwrap(), &[temp_file(name, contents)]).unwrap();
+}
+
+// runs `keys` on a fresh editor holding `contents`, drawing onto `screen`
+pub fn editor_on(screen: &mut Headless, name: &str, contents: &str, keys: &str) -> Editor {
+    let mut editor = editor(name, contents);
+    screen.feed_str(keys);
+    editor.run(screen);
+    return editor;
+}
+
+pub fn editor_after(name: &str, contents: &str, keys: &str) -> Editor {
+    return editor_on(&mut Headless::new(10, 40), name, contents, keys);
+}
+
+pub fn lines(editor: &Editor) -> Vec<String> {
+    let buffer = editor.buffer();
+    return (0..buffer.len_lines()).map(|row| buffer.line(row)).collect();
+}
+
+pub fn text(editor: &Editor) -> String {
+    return lines(editor).join("\n");
+}
+
+pub fn text_after(name: &str, contents: &str, keys: &str) -> String {
+    return text(&editor_after(name, contents, keys));
+}
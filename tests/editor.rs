@@ -0,0 +1,168 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use zin::backend::headless::Headless;
+use zin::Modes;
+
+use common::{editor, text};
+
+const ESC: i32 = 27;
+
+#[test]
+fn renders_text_status_bar_and_command_line() {
+    let mut editor = editor("render.txt", "hello\nworld\n");
+    let mut screen = Headless::new(6, 120);
+    editor.run(&mut screen);
+
+    assert_eq!(screen.line(0).trim_end(), "hello");
+    assert_eq!(screen.line(1).trim_end(), "world");
+    assert_eq!(screen.line(2).trim_end(), "~");
+    assert!(screen.line(4).starts_with(" NORMAL "));
+    assert!(screen.line(4).contains("render.txt"));
+    assert_eq!(screen.line(5).trim_end(), "");
+    assert_eq!(screen.cursor(), (0, 0));
+}
+
+#[test]
+fn typing_in_insert_mode_edits_the_buffer() {
+    let mut editor = editor("insert.txt", "hello\n");
+    let mut screen = Headless::new(6, 30);
+    screen.feed_str("iab");
+    editor.run(&mut screen);
+
+    assert_eq!(*editor.mode(), Modes::Insert);
+    assert!(screen.bar_cursor());
+    assert_eq!(screen.line(0).trim_end(), "abhello");
+    assert_eq!(screen.cursor(), (0, 2));
+
+    screen.feed_key(ESC);
+    editor.run(&mut screen);
+    assert_eq!(*editor.mode(), Modes::Normal);
+    assert!(!screen.bar_cursor());
+}
+
+#[test]
+fn multibyte_input_arrives_as_one_character() {
+    let mut editor = editor("multibyte.txt", "x\n");
+    let mut screen = Headless::new(6, 30);
+    screen.feed_str("i日ü");
+    editor.run(&mut screen);
+
+    assert_eq!(text(&editor), "日üx");
+    assert_eq!(editor.cursor(), (0, 2));
+    assert_eq!(screen.cell(0, 0).text, "日");
+    assert_eq!(screen.cell(0, 1).text, "");
+    assert_eq!(screen.cursor(), (0, 3));
+}
+
+#[test]
+fn tabs_are_drawn_to_the_next_tab_stop() {
+    let mut editor = editor("tabs.txt", "\tab\nx\tyz\n");
+    let mut screen = Headless::new(6, 30);
+    screen.feed_str("jll");
+    editor.run(&mut screen);
+
+    assert_eq!(screen.line(0).trim_end(), "        ab");
+    assert_eq!(screen.line(1).trim_end(), "x       yz");
+    assert_eq!(screen.cursor(), (1, 8));
+
+    screen.feed_str("k");
+    editor.run(&mut screen);
+    assert_eq!(editor.cursor(), (0, 1));
+    assert_eq!(screen.cursor(), (0, 8));
+}
+
+#[test]
+fn undo_reverts_a_whole_insert() {
+    let mut editor = editor("undo.txt", "hello\n");
+    let mut screen = Headless::new(6, 30);
+    screen.feed_str("ione two");
+    screen.feed_key(ESC);
+    editor.run(&mut screen);
+    assert_eq!(text(&editor), "one twohello");
+
+    screen.feed_str("u");
+    editor.run(&mut screen);
+    assert_eq!(text(&editor), "hello");
+
+    screen.feed_str("u");
+    editor.run(&mut screen);
+    assert_eq!(editor.command_line(), "Already at oldest change");
+}
+
+#[test]
+fn dd_deletes_the_line() {
+    let mut editor = editor("delete.txt", "one\ntwo\nthree\n");
+    let mut screen = Headless::new(6, 30);
+    screen.feed_key(ncurses::KEY_DOWN);
+    screen.feed_str("dd");
+    editor.run(&mut screen);
+
+    assert_eq!(text(&editor), "one\nthree");
+}
+
+#[test]
+fn vsplit_draws_both_windows() {
+    let mut editor = editor("split.txt", "left\n");
+    let mut screen = Headless::new(6, 21);
+    screen.feed_str(":vsplit\n");
+    editor.run(&mut screen);
+
+    assert_eq!(screen.line(0), "left      │left      ");
+    assert_eq!(screen.cell(0, 10).pair, 3);
+}
+
+#[test]
+fn resize_lays_the_screen_out_again() {
+    let mut editor = editor("resize.txt", "1\n2\n3\n4\n5\n6\n7\n8\n");
+    let mut screen = Headless::new(10, 30);
+    for _ in 0..7 {
+        screen.feed_key(ncurses::KEY_DOWN);
+    }
+    editor.run(&mut screen);
+
+    screen.resize(4, 12);
+    editor.run(&mut screen);
+    assert_eq!(screen.line(0).trim_end(), "7");
+    assert_eq!(screen.line(1).trim_end(), "8");
+    assert!(screen.line(2).starts_with(" NORMAL "));
+    assert_eq!(screen.cursor(), (1, 0));
+}
+
+#[test]
+fn quit_stops_the_editor() {
+    let mut editor = editor("quit.txt", "\n");
+    let mut screen = Headless::new(6, 30);
+    screen.feed_str(":q\nihello");
+    editor.run(&mut screen);
+
+    assert!(editor.should_quit());
+    assert_eq!(screen.pending_keys(), 6);
+}
+
+#[test]
+fn bracketed_paste_goes_in_verbatim_as_one_edit() {
+    let mut editor = editor("paste.txt", "x\n");
+    let mut screen = Headless::new(6, 30);
+    screen.feed_str("i\x1b[200~    a\r        b\r\x1b[201~y");
+    editor.run(&mut screen);
+    assert_eq!(text(&editor), "    a\n        b\nyx");
+    assert_eq!(*editor.mode(), Modes::Insert);
+
+    screen.feed_str("\x1bu");
+    editor.run(&mut screen);
+    assert_eq!(text(&editor), "x");
+
+    // outside insert mode a paste is dropped, not typed in
+    screen.feed_str("\x1b[200~dd\x1b[201~");
+    editor.run(&mut screen);
+    assert_eq!(*editor.mode(), Modes::Normal);
+    assert_eq!(text(&editor), "x");
+
+    // an escape sequence that is not a paste still reads as <Esc> and what followed it
+    screen.feed_str("i\x1b[2");
+    editor.run(&mut screen);
+    assert_eq!(*editor.mode(), Modes::Normal);
+    assert_eq!(text(&editor), "x");
+}
@@ -0,0 +1,93 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use zin::backend::headless::Headless;
+use zin::config::Config;
+use zin::syntax::Registry;
+use zin::Editor;
+
+use common::lines;
+
+// a small project in its own directory, with one file that .gitignore hides
+fn project(name: &str) -> PathBuf {
+    let dir = common::dir(name);
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join(".gitignore"), "ignored.txt\n").unwrap();
+    fs::write(dir.join("ignored.txt"), "needle\n").unwrap();
+    fs::write(dir.join("notes.txt"), "hay\n  a Needle here\nhay\n").unwrap();
+    fs::write(dir.join("src").join("main.rs"), "fn needle() {}\nfn main() { needle(); }\n").unwrap();
+    return dir;
+}
+
+fn editor_after(dir: &Path, keys: &str) -> Editor {
+    let path = dir.join("notes.txt").to_string_lossy().to_string();
+    let mut editor = Editor::new(Config::init(), Registry::load().unwrap(), &[path]).unwrap();
+    let mut screen = Headless::new(10, 60);
+    screen.feed_str(keys);
+    editor.run(&mut screen);
+    return editor;
+}
+
+#[test]
+fn results_are_listed_skipping_ignored_files() {
+    let dir = project("list");
+    let root = dir.to_string_lossy();
+    let editor = editor_after(&dir, &format!(":grep needle {}\n", root));
+
+    assert_eq!(editor.buffer().buf_name, "[grep]");
+    assert_eq!(editor.command_line(), "3 matches in 2 files");
+    assert_eq!(
+        lines(&editor),
+        [
+            format!("{}/notes.txt:2:5: a Needle here", root),
+            format!("{}/src/main.rs:1:4: fn needle() {{}}", root),
+            format!("{}/src/main.rs:2:13: fn main() {{ needle(); }}", root),
+        ]
+    );
+
+    let editor = editor_after(&dir, &format!(":grep Needle {}\n", root));
+    assert_eq!(editor.command_line(), "1 match in 1 file");
+    let editor = editor_after(&dir, &format!(":grep haystack {}\n", root));
+    assert_eq!(editor.command_line(), "No match: haystack");
+
+    // a pattern can hold spaces, the last word is only the path when it exists
+    let editor = editor_after(&dir, &format!(":grep fn main {}\n", root));
+    assert_eq!(editor.command_line(), "1 match in 1 file");
+    assert_eq!(lines(&editor), [format!("{}/src/main.rs:2:1: fn main() {{ needle(); }}", root)]);
+    let editor = editor_after(&dir, ":grep\n");
+    assert_eq!(editor.command_line(), "Usage: :grep <pattern> [path]");
+}
+
+#[test]
+fn enter_opens_a_result() {
+    let dir = project("enter");
+    let editor = editor_after(&dir, &format!(":grep needle {}\njj\n", dir.to_string_lossy()));
+    assert!(editor.buffer().buf_name.ends_with("main.rs"));
+    assert_eq!(editor.cursor(), (1, 12));
+    assert_eq!(editor.command_line(), "(3 of 3) fn main() { needle(); }");
+}
+
+#[test]
+fn cnext_and_cprev_step_through_results() {
+    let dir = project("step");
+    let grep = format!(":grep needle {}\n", dir.to_string_lossy());
+
+    let editor = editor_after(&dir, &format!("{}:cnext\n", grep));
+    assert!(editor.buffer().buf_name.ends_with("notes.txt"));
+    assert_eq!(editor.cursor(), (1, 4));
+
+    let editor = editor_after(&dir, &format!("{}:cn\n:cn\n", grep));
+    assert!(editor.buffer().buf_name.ends_with("main.rs"));
+    assert_eq!(editor.cursor(), (0, 3));
+
+    let editor = editor_after(&dir, &format!("{}:cn\n:cn\n:cn\n:cprev\n", grep));
+    assert_eq!(editor.cursor(), (0, 3));
+
+    let editor = editor_after(&dir, &format!("{}:cn\n:cn\n:cn\n:cn\n", grep));
+    assert_eq!(editor.command_line(), "No more items");
+    assert_eq!(editor.cursor(), (1, 12));
+}
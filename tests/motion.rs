@@ -0,0 +1,77 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::CODE;
+
+#[test]
+fn hjkl_with_counts() {
+    assert_eq!(common::editor_after("hjkl.rs", CODE, "jll").cursor(), (1, 2));
+    assert_eq!(common::editor_after("hjkl-count.rs", CODE, "3j2l").cursor(), (3, 2));
+    assert_eq!(common::editor_after("hjkl-clamp.rs", CODE, "20j").cursor(), (4, 0));
+    assert_eq!(common::editor_after("hjkl-left.rs", CODE, "$5h").cursor(), (0, 5));
+}
+
+#[test]
+fn vertical_motions_keep_the_column() {
+    let text = "abcdefgh\nab\n\nabcdefgh\n日本語x\n";
+    let cursor = |name: &str, keys: &str| common::editor_after(name, text, keys).cursor();
+    // short lines in between do not pull the cursor over to the left
+    assert_eq!(cursor("column-short.rs", "6lj"), (1, 1));
+    assert_eq!(cursor("column-empty.rs", "6ljj"), (2, 0));
+    assert_eq!(cursor("column-back.rs", "6ljjj"), (3, 6));
+    assert_eq!(cursor("column-count.rs", "6l3j"), (3, 6));
+    assert_eq!(cursor("column-up.rs", "6ljjjkk"), (1, 1));
+    // a move along the line sets the column again
+    assert_eq!(cursor("column-reset.rs", "6ljhjj"), (3, 0));
+    // the column is counted on screen, 本 is drawn over the third and fourth ones
+    assert_eq!(cursor("column-wide.rs", "3jllj"), (4, 1));
+    assert_eq!(cursor("column-dollar.rs", "$jjj"), (3, 7));
+    assert_eq!(cursor("column-end.rs", "$jjjj"), (4, 3));
+}
+
+#[test]
+fn word_motions() {
+    assert_eq!(common::editor_after("w.rs", CODE, "w").cursor(), (0, 3));
+    assert_eq!(common::editor_after("w-count.rs", CODE, "3w").cursor(), (0, 10));
+    assert_eq!(common::editor_after("w-lines.rs", CODE, "jwwwww").cursor(), (1, 19));
+    assert_eq!(common::editor_after("big-w.rs", CODE, "jwwwWW").cursor(), (1, 27));
+    assert_eq!(common::editor_after("w-empty.rs", CODE, "j$w").cursor(), (2, 0));
+    assert_eq!(common::editor_after("b.rs", CODE, "j$b").cursor(), (1, 28));
+    assert_eq!(common::editor_after("big-b.rs", CODE, "j$B").cursor(), (1, 27));
+    assert_eq!(common::editor_after("e.rs", CODE, "e").cursor(), (0, 1));
+    assert_eq!(common::editor_after("big-e.rs", CODE, "jwwwE").cursor(), (1, 25));
+}
+
+#[test]
+fn line_and_file_motions() {
+    assert_eq!(common::editor_after("dollar.rs", CODE, "$").cursor(), (0, 10));
+    assert_eq!(common::editor_after("caret.rs", CODE, "j$^").cursor(), (1, 4));
+    assert_eq!(common::editor_after("zero.rs", CODE, "j$0").cursor(), (1, 0));
+    assert_eq!(common::editor_after("big-g.rs", CODE, "G").cursor(), (4, 0));
+    assert_eq!(common::editor_after("gg.rs", CODE, "Ggg").cursor(), (0, 0));
+    assert_eq!(common::editor_after("count-g.rs", CODE, "2G").cursor(), (1, 4));
+}
+
+#[test]
+fn paragraph_and_bracket_motions() {
+    assert_eq!(common::editor_after("paragraph.rs", CODE, "}").cursor(), (2, 0));
+    assert_eq!(common::editor_after("paragraph-end.rs", CODE, "}}").cursor(), (4, 0));
+    assert_eq!(common::editor_after("paragraph-back.rs", CODE, "G{").cursor(), (2, 0));
+    assert_eq!(common::editor_after("percent.rs", CODE, "%").cursor(), (0, 8));
+    assert_eq!(common::editor_after("percent-lines.rs", CODE, "$%").cursor(), (4, 0));
+    assert_eq!(common::editor_after("percent-back.rs", CODE, "G%").cursor(), (0, 10));
+}
+
+#[test]
+fn character_finds_and_repeats() {
+    assert_eq!(common::editor_after("f.rs", CODE, "jf,").cursor(), (1, 25));
+    assert_eq!(common::editor_after("t.rs", CODE, "jt,").cursor(), (1, 24));
+    assert_eq!(common::editor_after("big-f.rs", CODE, "j$Fo").cursor(), (1, 18));
+    assert_eq!(common::editor_after("big-t.rs", CODE, "j$To").cursor(), (1, 19));
+    assert_eq!(common::editor_after("semicolon.rs", CODE, "jfo;").cursor(), (1, 17));
+    assert_eq!(common::editor_after("comma.rs", CODE, "jfo;,").cursor(), (1, 9));
+    assert_eq!(common::editor_after("t-repeat.rs", CODE, "jto;").cursor(), (1, 16));
+    assert_eq!(common::editor_after("f-count.rs", CODE, "j2fo").cursor(), (1, 17));
+    assert_eq!(common::editor_after("f-missing.rs", CODE, "jfz").cursor(), (1, 0));
+}
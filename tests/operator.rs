@@ -0,0 +1,55 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use zin::Modes;
+
+use common::CODE;
+
+#[test]
+fn delete_with_motions_and_counts() {
+    assert_eq!(common::editor_after("dw.rs", CODE, "jwdw").buffer().line(1), "    words = foo.bar(1, 2);");
+    assert_eq!(common::editor_after("d3w.rs", CODE, "jwd3w").buffer().line(1), "    foo.bar(1, 2);");
+    assert_eq!(common::editor_after("2d2w.rs", CODE, "jw2d2w").buffer().line(1), "    .bar(1, 2);");
+    assert_eq!(common::editor_after("d-dollar.rs", CODE, "jwwd$").buffer().line(1), "    let ");
+    assert_eq!(common::editor_after("dt.rs", CODE, "jdt(").buffer().line(1), "(1, 2);");
+    assert_eq!(common::editor_after("df.rs", CODE, "jwdf.").buffer().line(1), "    bar(1, 2);");
+    assert_eq!(common::editor_after("dw-end.rs", CODE, "j$bdw").buffer().line(1), "    let words = foo.bar(1, 2");
+    assert_eq!(common::editor_after("dl.rs", CODE, "$dl").buffer().line(0), "fn main() ");
+    assert_eq!(common::text_after("dj.rs", CODE, "dj"), "\n    call(x[0]);\n}");
+    assert_eq!(common::text_after("d-paragraph.rs", CODE, "d}"), "\n    call(x[0]);\n}");
+    assert_eq!(common::text_after("3dd.rs", CODE, "3dd"), "    call(x[0]);\n}");
+    assert_eq!(common::text_after("dG.rs", CODE, "jdG"), "fn main() {");
+}
+
+#[test]
+fn change_enters_insert_mode_as_one_undo_step() {
+    let editor = common::editor_after("cw.rs", CODE, "jwwcwthose\x1b");
+    assert_eq!(editor.buffer().line(1), "    let those = foo.bar(1, 2);");
+    assert_eq!(*editor.mode(), Modes::Normal);
+
+    assert_eq!(common::editor_after("c-dollar.rs", CODE, "jwc$x").buffer().line(1), "    x");
+    assert_eq!(common::editor_after("cc.rs", CODE, "jccx").buffer().line(1), "    x");
+    assert_eq!(common::text_after("cw-undo.rs", CODE, "jwcwthose\x1bu"), CODE.trim_end());
+}
+
+#[test]
+fn yank_indent_and_case() {
+    let editor = common::editor_after("y.rs", CODE, "jwy}");
+    assert_eq!(editor.cursor(), (1, 4));
+    assert_eq!(common::text(&editor), CODE.trim_end());
+
+    assert_eq!(common::text_after("indent.rs", CODE, ">j"), "    fn main() {\n        let words = foo.bar(1, 2);\n\n    call(x[0]);\n}");
+    assert_eq!(common::editor_after("outdent.rs", CODE, "j<<").buffer().line(1), "let words = foo.bar(1, 2);");
+    assert_eq!(common::editor_after("indent-count.rs", CODE, "j2>>").buffer().line(2), "");
+    assert_eq!(common::editor_after("upper.rs", CODE, "jwgUe").buffer().line(1), "    LET words = foo.bar(1, 2);");
+    assert_eq!(common::editor_after("upper-line.rs", CODE, "gUU").buffer().line(0), "FN MAIN() {");
+    assert_eq!(common::editor_after("lower.rs", CODE, "gUUgu$").buffer().line(0), "fn main() {");
+}
+
+#[test]
+fn escape_cancels_a_pending_operator() {
+    let editor = common::editor_after("cancel.rs", CODE, "d\x1bj");
+    assert_eq!(common::text(&editor), CODE.trim_end());
+    assert_eq!(editor.cursor(), (1, 0));
+}
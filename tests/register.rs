@@ -0,0 +1,54 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use zin::backend::headless::Headless;
+
+const TEXT: &str = "one two\nthree\nfour\n";
+
+#[test]
+fn charwise_text_goes_after_or_before_the_cursor() {
+    assert_eq!(common::text_after("p-char.txt", TEXT, "yejp"), "one two\ntonehree\nfour");
+    assert_eq!(common::text_after("big-p-char.txt", TEXT, "yejP"), "one two\nonethree\nfour");
+    assert_eq!(common::text_after("p-count.txt", TEXT, "yw$3p"), "one twoone one one \nthree\nfour");
+    assert_eq!(common::editor_after("p-cursor.txt", TEXT, "yejp").cursor(), (1, 3));
+    assert_eq!(common::text_after("p-multiline.txt", TEXT, "wvjyGp"), "one two\nthree\nftwo\nthreeour");
+}
+
+#[test]
+fn linewise_text_goes_below_or_above_the_line() {
+    assert_eq!(common::text_after("p-line.txt", TEXT, "yyjp"), "one two\nthree\none two\nfour");
+    assert_eq!(common::text_after("big-p-line.txt", TEXT, "yyjP"), "one two\none two\nthree\nfour");
+    assert_eq!(common::text_after("p-line-end.txt", TEXT, "yyG2p"), "one two\nthree\nfour\none two\none two");
+    assert_eq!(common::editor_after("p-line-cursor.txt", TEXT, "jyyp").cursor(), (2, 0));
+}
+
+#[test]
+fn reverse_and_block_selections() {
+    assert_eq!(common::text_after("reverse.txt", TEXT, "jlvkyGP"), "one two\nthree\nne two\nthfour");
+    assert_eq!(common::text_after("reverse-line.txt", TEXT, "jVkdp"), "four\none two\nthree");
+    assert_eq!(common::text_after("block-p.txt", TEXT, "\x16jlyGp"), "one two\nthree\nfonour\n th");
+    assert_eq!(common::text_after("block-big-p.txt", TEXT, "l\x16jlyP"), "onene two\nthrhree\nfour");
+}
+
+#[test]
+fn named_numbered_and_black_hole_registers() {
+    assert_eq!(common::text_after("named.txt", TEXT, "\"ayyjdd\"aP"), "one two\none two\nfour");
+    assert_eq!(common::text_after("append.txt", TEXT, "\"ayej\"Ayy\"ap"), "one two\nthree\none\nthree\nfour");
+    assert_eq!(common::text_after("numbered.txt", TEXT, "yyjddjdd\"0P\"2p"), "one two\nthree\none two");
+    assert_eq!(common::text_after("black-hole.txt", TEXT, "yyj\"_ddp"), "one two\nfour\none two");
+    assert_eq!(common::text_after("p-keeps.txt", TEXT, "yejpp"), "one two\ntoneonehree\nfour");
+}
+
+#[test]
+fn registers_command_lists_contents() {
+    let mut screen = Headless::new(10, 40);
+    let mut editor = common::editor_on(&mut screen, "list.txt", TEXT, "\"byejyy:registers\n");
+    assert_eq!(editor.command_line(), "Type Name Content\n  l  \"\"   three^J\n  l  \"0   three^J\n  c  \"b   one");
+    assert_eq!(screen.line(6).trim_end(), "Type Name Content");
+    assert_eq!(screen.line(9).trim_end(), "  c  \"b   one");
+
+    screen.feed_str("j");
+    editor.run(&mut screen);
+    assert_eq!(editor.command_line(), "");
+}
@@ -0,0 +1,69 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use zin::backend::headless::Headless;
+use zin::Modes;
+
+const TEXT: &str = "one two one\nTwo three\nfour one two\n";
+
+#[test]
+fn every_match_on_a_line_is_found_and_n_wraps() {
+    let editor = common::editor_after("forward.txt", TEXT, "/one\n");
+    assert_eq!((editor.cursor(), editor.command_line()), ((0, 8), "/one"));
+    let editor = common::editor_after("forward_n.txt", TEXT, "/one\nn");
+    assert_eq!((editor.cursor(), editor.command_line()), ((2, 5), "/one"));
+    let editor = common::editor_after("wrap.txt", TEXT, "/one\nnn");
+    assert_eq!((editor.cursor(), editor.command_line()), ((0, 0), "search hit BOTTOM, continuing at TOP"));
+    let editor = common::editor_after("count.txt", TEXT, "/one\n2n");
+    assert_eq!((editor.cursor(), editor.command_line()), ((0, 0), "search hit BOTTOM, continuing at TOP"));
+    let editor = common::editor_after("missing.txt", TEXT, "/five\n");
+    assert_eq!((editor.cursor(), editor.command_line()), ((0, 0), "Pattern not found: five"));
+}
+
+#[test]
+fn question_mark_searches_backward_and_n_follows_it() {
+    let editor = common::editor_after("backward.txt", TEXT, "?one\n");
+    assert_eq!((editor.cursor(), editor.command_line()), ((2, 5), "search hit TOP, continuing at BOTTOM"));
+    let editor = common::editor_after("backward_n.txt", TEXT, "?one\nn");
+    assert_eq!((editor.cursor(), editor.command_line()), ((0, 8), "?one"));
+    let editor = common::editor_after("backward_big_n.txt", TEXT, "?one\nnN");
+    assert_eq!((editor.cursor(), editor.command_line()), ((2, 5), "/one"));
+}
+
+#[test]
+fn patterns_are_regexes_with_smart_case() {
+    assert_eq!(common::editor_after("regex.txt", TEXT, "/t\\w+e\n").cursor(), (1, 4));
+    assert_eq!(common::editor_after("spaces.txt", TEXT, "/one tw\n").cursor(), (2, 5));
+    assert_eq!(common::editor_after("lower.txt", TEXT, "/two\nn").cursor(), (1, 0));
+    assert_eq!(common::editor_after("upper.txt", TEXT, "/Two\nn").cursor(), (1, 0));
+    assert_eq!(common::editor_after("upper_only.txt", TEXT, "/Two\nn").command_line(), "search hit BOTTOM, continuing at TOP");
+    assert_eq!(common::editor_after("again.txt", TEXT, "/two\n/\n").cursor(), (1, 0));
+}
+
+#[test]
+fn matches_are_highlighted_while_typing() {
+    let mut screen = Headless::new(10, 40);
+    let editor = common::editor_on(&mut screen, "incremental.txt", TEXT, "/on");
+    assert_eq!(*editor.mode(), Modes::Command);
+    assert_eq!(editor.cursor(), (0, 8));
+    assert_eq!(screen.cell(0, 0).pair, 17);
+    assert_eq!(screen.cell(0, 1).pair, 17);
+    assert_eq!(screen.cell(0, 2).pair, 1);
+    assert_eq!(screen.cell(0, 8).pair, 17);
+    assert_eq!(screen.cell(2, 5).pair, 17);
+
+    // <Esc> goes back to where the search started and drops the highlight
+    let mut screen = Headless::new(10, 40);
+    let editor = common::editor_on(&mut screen, "cancel.txt", TEXT, "j/on\x1b");
+    assert_eq!(editor.cursor(), (1, 0));
+    assert_eq!(screen.cell(0, 0).pair, 1);
+
+    // a finished search stays highlighted until :noh
+    let mut screen = Headless::new(10, 40);
+    common::editor_on(&mut screen, "kept.txt", TEXT, "/one\n");
+    assert_eq!(screen.cell(2, 5).pair, 17);
+    let mut screen = Headless::new(10, 40);
+    common::editor_on(&mut screen, "noh.txt", TEXT, "/one\n:noh\n");
+    assert_eq!(screen.cell(2, 5).pair, 1);
+}
@@ -0,0 +1,59 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+const TEXT: &str = "foo bar foo\nbar foo\nFoo baz\nfoo\n";
+
+#[test]
+fn ranges_pick_the_lines() {
+    assert_eq!(common::text_after("line.txt", TEXT, ":s/foo/x/\n"), "x bar foo\nbar foo\nFoo baz\nfoo");
+    assert_eq!(common::text_after("all.txt", TEXT, ":%s/foo/x/\n"), "x bar foo\nbar x\nFoo baz\nx");
+    assert_eq!(common::text_after("numbers.txt", TEXT, ":2,4s/foo/x/\n"), "foo bar foo\nbar x\nFoo baz\nx");
+    assert_eq!(common::text_after("dollar.txt", TEXT, "j:.,$s/foo/x/\n"), "foo bar foo\nbar x\nFoo baz\nx");
+    assert_eq!(common::text_after("visual.txt", TEXT, "jVj:s/bar/x/\n"), "foo bar foo\nx foo\nFoo baz\nfoo");
+
+    let editor = common::editor_after("invalid.txt", TEXT, ":3,9s/foo/x/\n");
+    assert_eq!(editor.command_line(), "Invalid range");
+}
+
+#[test]
+fn flags_and_groups() {
+    assert_eq!(common::text_after("global.txt", TEXT, ":%s/foo/x/g\n"), "x bar x\nbar x\nFoo baz\nx");
+    assert_eq!(common::text_after("ignore.txt", TEXT, ":3s/foo/x/i\n"), "foo bar foo\nbar foo\nx baz\nfoo");
+    assert_eq!(common::text_after("groups.txt", TEXT, ":1s/(\\w+) (\\w+)/\\2 \\1/\n"), "bar foo foo\nbar foo\nFoo baz\nfoo");
+    assert_eq!(common::text_after("whole.txt", TEXT, ":2s/foo/<&>/\n"), "foo bar foo\nbar <foo>\nFoo baz\nfoo");
+    assert_eq!(common::text_after("delimiter.txt", TEXT, ":4s#foo#a/b#\n"), "foo bar foo\nbar foo\nFoo baz\na/b");
+    assert_eq!(common::text_after("empty.txt", TEXT, ":4s/o*/-/g\n"), "foo bar foo\nbar foo\nFoo baz\n-f-");
+
+    // \r and \n break the line, the lines they make are not searched again
+    assert_eq!(common::text_after("break.txt", TEXT, ":1,2s/ /\\r/g\n"), "foo\nbar\nfoo\nbar\nfoo\nFoo baz\nfoo");
+    assert_eq!(common::text_after("newline.txt", TEXT, ":%s/foo/\\n&/g\n"), "\nfoo bar \nfoo\nbar \nfoo\nFoo baz\n\nfoo");
+    assert_eq!(common::text_after("tab.txt", TEXT, ":3s/ /\\t/\n"), "foo bar foo\nbar foo\nFoo\tbaz\nfoo");
+    let editor = common::editor_after("break_report.txt", TEXT, ":%s/foo/x\\ry/g\n");
+    assert_eq!(editor.command_line(), "4 substitutions on 3 lines");
+    assert_eq!(editor.cursor(), (7, 0));
+
+    let editor = common::editor_after("report.txt", TEXT, ":%s/foo/x/g\n");
+    assert_eq!(editor.command_line(), "4 substitutions on 3 lines");
+    assert_eq!(editor.cursor(), (3, 0));
+    let editor = common::editor_after("not_found.txt", TEXT, ":%s/qux/x/\n");
+    assert_eq!(editor.command_line(), "Pattern not found: qux");
+}
+
+#[test]
+fn confirm_asks_about_every_match() {
+    let editor = common::editor_after("ask.txt", TEXT, ":%s/foo/x/gc\n");
+    assert_eq!(editor.command_line(), "replace with x (y/n/a/q/l)?");
+    assert_eq!(editor.cursor(), (0, 0));
+
+    assert_eq!(common::text_after("yes_no.txt", TEXT, ":%s/foo/x/gc\nynyq"), "x bar foo\nbar x\nFoo baz\nfoo");
+    assert_eq!(common::text_after("all_after.txt", TEXT, ":%s/foo/x/gc\nna"), "foo bar x\nbar x\nFoo baz\nx");
+    assert_eq!(common::text_after("last.txt", TEXT, ":%s/foo/x/gc\nnl"), "foo bar x\nbar foo\nFoo baz\nfoo");
+    assert_eq!(common::text_after("escape.txt", TEXT, ":%s/foo/x/gc\ny\x1b"), "x bar foo\nbar foo\nFoo baz\nfoo");
+}
+
+#[test]
+fn one_substitution_is_one_undo_step() {
+    assert_eq!(common::text_after("undo.txt", TEXT, ":%s/foo/x/g\nu"), TEXT.trim_end());
+    assert_eq!(common::text_after("undo_confirm.txt", TEXT, ":%s/foo/x/gc\nyya\x1bu"), TEXT.trim_end());
+}
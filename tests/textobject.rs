@@ -0,0 +1,48 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+const TEXT: &str = "let s = call(\"a b\", [1, (2)]);\n\nfn f() {\n    body();\n}\n<p>one <b>two</b></p>\n";
+
+#[test]
+fn words() {
+    assert_eq!(common::editor_after("diw.rs", TEXT, "wdiw").buffer().line(0), "let  = call(\"a b\", [1, (2)]);");
+    assert_eq!(common::editor_after("daw.rs", TEXT, "wdaw").buffer().line(0), "let = call(\"a b\", [1, (2)]);");
+    assert_eq!(common::editor_after("d3iw.rs", TEXT, "d3iw").buffer().line(0), " = call(\"a b\", [1, (2)]);");
+    assert_eq!(common::editor_after("daw-end.rs", TEXT, "$daw").buffer().line(0), "let s = call(\"a b\", [1, (2");
+    assert_eq!(common::editor_after("ciw.rs", TEXT, "wciwx\x1b").buffer().line(0), "let x = call(\"a b\", [1, (2)]);");
+    assert_eq!(common::editor_after("diW.rs", TEXT, "f\"diW").buffer().line(0), "let s =  b\", [1, (2)]);");
+}
+
+#[test]
+fn quotes_and_brackets() {
+    assert_eq!(common::editor_after("di-quote.rs", TEXT, "di\"").buffer().line(0), "let s = call(\"\", [1, (2)]);");
+    assert_eq!(common::editor_after("da-quote.rs", TEXT, "fbda\"").buffer().line(0), "let s = call(, [1, (2)]);");
+    assert_eq!(common::editor_after("di-paren.rs", TEXT, "fbdi(").buffer().line(0), "let s = call();");
+    assert_eq!(common::editor_after("da-paren.rs", TEXT, "fbda(").buffer().line(0), "let s = call;");
+    assert_eq!(common::editor_after("di-bracket.rs", TEXT, "f2di]").buffer().line(0), "let s = call(\"a b\", []);");
+    assert_eq!(common::editor_after("d2i-paren.rs", TEXT, "f2d2i(").buffer().line(0), "let s = call();");
+    assert_eq!(common::editor_after("di-on-close.rs", TEXT, "f)di(").buffer().line(0), "let s = call(\"a b\", [1, ()]);");
+    assert_eq!(common::editor_after("d3i-paren.rs", TEXT, "f2d3i(").buffer().line(0), "let s = call(\"a b\", [1, (2)]);");
+    assert_eq!(common::text_after("di-brace.rs", TEXT, "jjjdiB"), "let s = call(\"a b\", [1, (2)]);\n\nfn f() {\n}\n<p>one <b>two</b></p>");
+}
+
+#[test]
+fn paragraphs_and_tags() {
+    assert_eq!(common::text_after("dip.rs", TEXT, "dip"), "\nfn f() {\n    body();\n}\n<p>one <b>two</b></p>");
+    assert_eq!(common::text_after("dap.rs", TEXT, "dap"), "fn f() {\n    body();\n}\n<p>one <b>two</b></p>");
+    assert_eq!(common::text_after("dit.rs", TEXT, "Gftdit"), "let s = call(\"a b\", [1, (2)]);\n\nfn f() {\n    body();\n}\n<p>one <b></b></p>");
+    assert_eq!(common::text_after("dat.rs", TEXT, "Gftdat"), "let s = call(\"a b\", [1, (2)]);\n\nfn f() {\n    body();\n}\n<p>one </p>");
+    assert_eq!(common::text_after("d2it.rs", TEXT, "Gftd2it"), "let s = call(\"a b\", [1, (2)]);\n\nfn f() {\n    body();\n}\n<p></p>");
+    assert_eq!(common::text_after("d3it.rs", TEXT, "Gftd3it"), TEXT.trim_end());
+    assert_eq!(common::text_after("dit-outside.rs", TEXT, "dit"), TEXT.trim_end());
+    // a bare `<` compares, it doesn't open a tag that runs on to the next `>`
+    assert_eq!(common::text_after("dit-compare.rs", "a < b <div>x</div>\n", "fxdit"), "a < b <div></div>");
+    assert_eq!(common::text_after("dit-lines.rs", "if a <\nb { <i>x</i> }\n", "jfxdit"), "if a <\nb { <i></i> }");
+}
+
+#[test]
+fn visual_selection_grows_over_an_object() {
+    assert_eq!(common::editor_after("viw.rs", TEXT, "wviwy").buffer().line(0), "let s = call(\"a b\", [1, (2)]);");
+    assert_eq!(common::editor_after("va-paren.rs", TEXT, "fbva(y0P").buffer().line(0), "(\"a b\", [1, (2)])let s = call(\"a b\", [1, (2)]);");
+}
@@ -0,0 +1,50 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use zin::backend::headless::Headless;
+use zin::Modes;
+
+const TEXT: &str = "alpha beta\ngamma delta\nepsilon\n";
+const CTRL_V: &str = "\x16";
+
+#[test]
+fn selection_is_highlighted() {
+    let mut screen = Headless::new(10, 40);
+    common::editor_on(&mut screen, "highlight.txt", TEXT, "wvj");
+    assert_eq!(screen.cell(0, 5).pair, 1);
+    assert_eq!(screen.cell(0, 6).pair, 16);
+    assert_eq!(screen.cell(0, 10).pair, 16);
+    assert_eq!(screen.cell(1, 6).pair, 16);
+    assert_eq!(screen.cell(1, 7).pair, 1);
+    assert!(screen.line(8).starts_with(" VISUAL "));
+
+    let mut screen = Headless::new(10, 40);
+    common::editor_on(&mut screen, "highlight-block.txt", TEXT, &format!("l{}jl", CTRL_V));
+    assert_eq!(screen.cell(0, 0).pair, 1);
+    assert_eq!(screen.cell(0, 2).pair, 16);
+    assert_eq!(screen.cell(1, 1).pair, 16);
+    assert_eq!(screen.cell(1, 3).pair, 1);
+    assert!(screen.line(8).starts_with(" V-BLOCK "));
+}
+
+#[test]
+fn charwise_and_linewise_operators() {
+    assert_eq!(common::text_after("v-d.txt", TEXT, "wvjd"), "alpha elta\nepsilon");
+    assert_eq!(common::text_after("v-c.txt", TEXT, "vecomega\x1b"), "omega beta\ngamma delta\nepsilon");
+    assert_eq!(common::text_after("big-v-d.txt", TEXT, "Vjd"), "epsilon");
+    assert_eq!(common::text_after("big-v-indent.txt", TEXT, "jVj>"), "alpha beta\n    gamma delta\n    epsilon");
+    assert_eq!(common::text_after("big-v-yank.txt", TEXT, "Vyjp"), "alpha beta\ngamma delta\nalpha beta\nepsilon");
+
+    let editor = common::editor_after("v-toggle.txt", TEXT, "vVV");
+    assert_eq!(*editor.mode(), Modes::Normal);
+}
+
+#[test]
+fn blockwise_operators() {
+    assert_eq!(common::text_after("block-d.txt", TEXT, &format!("l{}jjld", CTRL_V)), "aha beta\ngma delta\neilon");
+    assert_eq!(common::text_after("block-i.txt", TEXT, &format!("l{}jjI- \x1b", CTRL_V)), "a- lpha beta\ng- amma delta\ne- psilon");
+    assert_eq!(common::text_after("block-a.txt", TEXT, &format!("$l{}jjA;\x1b", CTRL_V)), "alpha beta;\ngamma delt;a\nepsilon   ;");
+    assert_eq!(common::text_after("block-c.txt", TEXT, &format!("{}jcx\x1b", CTRL_V)), "xlpha beta\nxamma delta\nepsilon");
+    assert_eq!(common::text_after("block-undo.txt", TEXT, &format!("l{}jjI- \x1bu", CTRL_V)), TEXT.trim_end());
+}